@@ -0,0 +1,176 @@
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{data_dir, read_data_file};
+use crate::hn_web::{ItemAction, ItemLinks, Session};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct AccountState {
+    session: Option<Session>,
+    // What the account has done on HN, as far as the app has seen: through
+    // its own actions and the item pages it has loaded.
+    upvoted: HashSet<u32>,
+    favorites: HashSet<u32>,
+    hidden: HashSet<u32>,
+}
+
+// The logged-in HN account, persisted as JSON in the data directory. The file
+// holds the session cookie, so on Unix only its owner can read it.
+#[derive(Default)]
+pub struct Account {
+    state: AccountState,
+    path: Option<PathBuf>,
+}
+
+impl Account {
+    pub fn load() -> Result<Account, String> {
+        let path = data_dir().map(|dir| dir.join("account.json"));
+        let state = match &path {
+            Some(path) => read_data_file(path, "account")?,
+            None => AccountState::default(),
+        };
+        Ok(Account { state, path })
+    }
+
+    // Written to a new file that is private from the start, then moved over
+    // the old one, so the cookie is never readable by others, not even
+    // briefly or in a file an older version left world-readable.
+    pub fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Err("No data directory available to save account".to_string());
+        };
+        let failed =
+            |e: std::io::Error| format!("Failed to save account {}: {}", path.display(), e);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(failed)?;
+        }
+        let json = serde_json::to_string_pretty(&self.state).map_err(|e| e.to_string())?;
+        let temp = path.with_extension("json.tmp");
+        // Left over from a save that was cut short.
+        let _ = std::fs::remove_file(&temp);
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options
+            .open(&temp)
+            .and_then(|mut file| file.write_all(json.as_bytes()))
+            .and_then(|()| std::fs::rename(&temp, path))
+            .map_err(failed)
+    }
+
+    pub fn session(&self) -> Option<&Session> {
+        self.state.session.as_ref()
+    }
+
+    // Starts a session. What was recorded for another user is dropped.
+    pub fn log_in(&mut self, session: Session) -> Result<(), String> {
+        let same_user = self
+            .session()
+            .is_some_and(|s| s.username == session.username);
+        if !same_user {
+            self.state = AccountState::default();
+        }
+        self.state.session = Some(session);
+        self.save()
+    }
+
+    // Forgets the session cookie and everything recorded for the account.
+    pub fn log_out(&mut self) -> Result<(), String> {
+        self.state = AccountState::default();
+        self.save()
+    }
+
+    pub fn is_upvoted(&self, id: u32) -> bool {
+        self.state.upvoted.contains(&id)
+    }
+
+    pub fn is_favorite(&self, id: u32) -> bool {
+        self.state.favorites.contains(&id)
+    }
+
+    pub fn is_hidden(&self, id: u32) -> bool {
+        self.state.hidden.contains(&id)
+    }
+
+    // Records an action HN has accepted.
+    pub fn record(&mut self, id: u32, action: ItemAction) -> Result<(), String> {
+        let (set, on) = match action {
+            ItemAction::Upvote => (&mut self.state.upvoted, true),
+            ItemAction::Unvote => (&mut self.state.upvoted, false),
+            ItemAction::Favorite => (&mut self.state.favorites, true),
+            ItemAction::Unfavorite => (&mut self.state.favorites, false),
+            ItemAction::Hide => (&mut self.state.hidden, true),
+            ItemAction::Unhide => (&mut self.state.hidden, false),
+        };
+        toggle(set, id, on);
+        self.save()
+    }
+
+    // Takes vote and favorite state from an item page, which also covers
+    // things done in the browser. Saved only if anything changed.
+    pub fn sync(&mut self, links: &ItemLinks) -> Result<(), String> {
+        let mut changed = false;
+        for (id, upvoted) in links.votes() {
+            changed |= toggle(&mut self.state.upvoted, id, upvoted);
+        }
+        for (id, favorite) in links.favorites() {
+            changed |= toggle(&mut self.state.favorites, id, favorite);
+        }
+        if changed {
+            self.save()?;
+        }
+        Ok(())
+    }
+}
+
+// Puts `id` in or out of `set`, returning whether that changed it.
+fn toggle(set: &mut HashSet<u32>, id: u32, on: bool) -> bool {
+    if on {
+        set.insert(id)
+    } else {
+        set.remove(&id)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn saves_the_cookie_readable_only_by_the_owner() {
+        let dir = std::env::temp_dir().join(format!("hackertuah-account-{}", std::process::id()));
+        let path = dir.join("account.json");
+        std::fs::create_dir_all(&dir).unwrap();
+        // As left by a version that wrote it with the default umask.
+        std::fs::write(&path, "{}").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        let mut account = Account {
+            state: AccountState::default(),
+            path: Some(path.clone()),
+        };
+        account
+            .log_in(Session {
+                username: "alice".to_string(),
+                cookie: "user=alice&t0k".to_string(),
+            })
+            .unwrap();
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(saved.contains("user=alice&t0k"));
+    }
+}
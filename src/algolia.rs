@@ -0,0 +1,494 @@
+use serde::Deserialize;
+
+use crate::diagnostics;
+use crate::error::ApiError;
+use crate::http::{self, HttpClient};
+
+pub const DEFAULT_BASE_URL: &str = "https://hn.algolia.com/api/v1";
+
+const HITS_PER_PAGE: u32 = 50;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum SearchKind {
+    Stories,
+    Comments,
+}
+
+impl SearchKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchKind::Stories => "stories",
+            SearchKind::Comments => "comments",
+        }
+    }
+
+    pub fn toggle(&self) -> SearchKind {
+        match self {
+            SearchKind::Stories => SearchKind::Comments,
+            SearchKind::Comments => SearchKind::Stories,
+        }
+    }
+
+    fn tag(&self) -> &'static str {
+        match self {
+            SearchKind::Stories => "story",
+            SearchKind::Comments => "comment",
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum SearchSort {
+    Relevance,
+    Date,
+}
+
+impl SearchSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchSort::Relevance => "relevance",
+            SearchSort::Date => "date",
+        }
+    }
+
+    pub fn toggle(&self) -> SearchSort {
+        match self {
+            SearchSort::Relevance => SearchSort::Date,
+            SearchSort::Date => SearchSort::Relevance,
+        }
+    }
+
+    fn endpoint(&self) -> &'static str {
+        match self {
+            SearchSort::Relevance => "search",
+            SearchSort::Date => "search_by_date",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SearchQuery {
+    pub text: String,
+    pub kind: SearchKind,
+    pub sort: SearchSort,
+    // Unix seconds, inclusive.
+    pub after: Option<u64>,
+    pub before: Option<u64>,
+    pub min_points: Option<u32>,
+    // Only items posted by this user.
+    pub author: Option<String>,
+    // Only stories linking to this domain.
+    pub site: Option<String>,
+}
+
+impl SearchQuery {
+    // Splits filter tokens out of what was typed in the search box:
+    // `points>100` (or `points>=100`), `after:2024-01-31`,
+    // `before:2024-12-31`, `by:name` and `site:example.com`. Everything else
+    // is the full-text query.
+    pub fn parse(input: &str, kind: SearchKind, sort: SearchSort) -> Result<SearchQuery, String> {
+        let mut query = SearchQuery {
+            text: String::new(),
+            kind,
+            sort,
+            after: None,
+            before: None,
+            min_points: None,
+            author: None,
+            site: None,
+        };
+        let mut words = Vec::new();
+        for word in input.split_whitespace() {
+            if let Some(value) = word.strip_prefix("after:") {
+                query.after = Some(parse_date(value)?);
+            } else if let Some(value) = word.strip_prefix("before:") {
+                // The whole day is included.
+                query.before = Some(parse_date(value)? + 86_399);
+            } else if let Some(value) = word
+                .strip_prefix("by:")
+                .or_else(|| word.strip_prefix("author:"))
+            {
+                query.author = Some(value.to_string());
+            } else if let Some(value) = word.strip_prefix("site:") {
+                query.site = Some(value.to_string());
+            } else if let Some(value) = word.strip_prefix("points>=") {
+                query.min_points = Some(parse_points(value)?);
+            } else if let Some(value) = word.strip_prefix("points>") {
+                let points = parse_points(value)?
+                    .checked_add(1)
+                    .ok_or_else(|| format!("invalid points threshold '{}'", value))?;
+                query.min_points = Some(points);
+            } else {
+                words.push(word);
+            }
+        }
+        query.text = words.join(" ");
+        Ok(query)
+    }
+}
+
+fn parse_points(value: &str) -> Result<u32, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid points threshold '{}'", value))
+}
+
+// Parses `YYYY-MM-DD` (UTC) into Unix seconds at the start of that day.
+pub fn parse_date(value: &str) -> Result<u64, String> {
+    let invalid = || format!("invalid date '{}' (expected YYYY-MM-DD)", value);
+    let mut parts = value.splitn(3, '-').map(|p| p.parse::<i64>());
+    let (Some(Ok(year)), Some(Ok(month)), Some(Ok(day))) =
+        (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid());
+    };
+    if !(1..=12).contains(&month) || year < 1970 {
+        return Err(invalid());
+    }
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let month_days = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+    if !(1..=month_days).contains(&day) {
+        return Err(invalid());
+    }
+    // Days since the epoch, from Howard Hinnant's `days_from_civil`.
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    Ok(days as u64 * 86_400)
+}
+
+// Formats Unix seconds as `YYYY-MM-DD` (UTC).
+pub fn format_date(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// One story or comment returned by the search API.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SearchHit {
+    #[serde(rename = "objectID")]
+    pub object_id: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub points: Option<i32>,
+    #[serde(default)]
+    pub num_comments: Option<u32>,
+    #[serde(default)]
+    pub created_at_i: u64,
+    // Set on comments: the story they belong to.
+    #[serde(default)]
+    pub story_id: Option<u32>,
+    #[serde(default)]
+    pub story_title: Option<String>,
+    #[serde(default)]
+    pub comment_text: Option<String>,
+}
+
+impl SearchHit {
+    pub fn id(&self) -> Option<u32> {
+        self.object_id.parse().ok()
+    }
+
+    pub fn is_comment(&self) -> bool {
+        self.comment_text.is_some()
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SearchResults {
+    pub hits: Vec<SearchHit>,
+    #[serde(default, rename = "nbHits")]
+    pub total: u64,
+}
+
+// Client for the Algolia HN Search API. The base URL is configurable so it
+// can point at a local stand-in.
+#[derive(Clone)]
+pub struct SearchClient {
+    http: HttpClient,
+    base_url: String,
+}
+
+impl Default for SearchClient {
+    fn default() -> Self {
+        Self::new(DEFAULT_BASE_URL)
+    }
+}
+
+impl SearchClient {
+    pub fn new(base_url: &str) -> SearchClient {
+        SearchClient {
+            http: http::client().clone(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub async fn search(&self, query: &SearchQuery) -> Result<SearchResults, ApiError> {
+        let _task = diagnostics::start_task("HN search");
+        let mut filters = Vec::new();
+        if let Some(after) = query.after {
+            filters.push(format!("created_at_i>={}", after));
+        }
+        if let Some(before) = query.before {
+            filters.push(format!("created_at_i<={}", before));
+        }
+        if let Some(points) = query.min_points {
+            filters.push(format!("points>={}", points));
+        }
+        // Comma-separated tags must all match.
+        let tags = match &query.author {
+            Some(author) => format!("{},author_{}", query.kind.tag(), author),
+            None => query.kind.tag().to_string(),
+        };
+        // Algolia has no domain filter, so `site:` searches the URLs instead
+        // of the titles and text, for the other words as well.
+        let text = match &query.site {
+            Some(site) => format!("{} {}", site, query.text).trim_end().to_string(),
+            None => query.text.clone(),
+        };
+        let mut params = vec![
+            ("query", text),
+            ("tags", tags),
+            ("hitsPerPage", HITS_PER_PAGE.to_string()),
+        ];
+        if !filters.is_empty() {
+            params.push(("numericFilters", filters.join(",")));
+        }
+        if query.site.is_some() {
+            params.push(("restrictSearchableAttributes", "url".to_string()));
+        }
+        let url = format!("{}/{}", self.base_url, query.sort.endpoint());
+        let results = self
+            .http
+            .send_idempotent(|client| client.get(&url).query(&params))
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(results)
+    }
+}
+
+// State of the remote search screen. Results stay around after leaving it so
+// coming back from a thread shows the same list.
+pub struct RemoteSearch {
+    pub client: SearchClient,
+    pub input: String,
+    pub kind: SearchKind,
+    pub sort: SearchSort,
+    // Input, kind and sort the current results were fetched for.
+    pub last_run: Option<(String, SearchKind, SearchSort)>,
+    pub results: Vec<SearchHit>,
+    pub total: u64,
+    pub selected: usize,
+    pub scroll: usize,
+}
+
+impl Default for RemoteSearch {
+    fn default() -> Self {
+        RemoteSearch {
+            client: SearchClient::default(),
+            input: String::new(),
+            kind: SearchKind::Stories,
+            sort: SearchSort::Relevance,
+            last_run: None,
+            results: Vec::new(),
+            total: 0,
+            selected: 0,
+            scroll: 0,
+        }
+    }
+}
+
+impl RemoteSearch {
+    // Whether the results are out of date with what's in the search box.
+    pub fn is_stale(&self) -> bool {
+        self.last_run.as_ref().is_none_or(|(input, kind, sort)| {
+            input != &self.input || *kind != self.kind || *sort != self.sort
+        })
+    }
+
+    pub fn next(&mut self) {
+        if !self.results.is_empty() {
+            self.selected = (self.selected + 1) % self.results.len();
+        }
+    }
+
+    pub fn previous(&mut self) {
+        if !self.results.is_empty() {
+            self.selected = self
+                .selected
+                .checked_sub(1)
+                .unwrap_or(self.results.len() - 1);
+        }
+    }
+
+    pub fn ensure_visible(&mut self, height: usize) {
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if height > 0 && self.selected >= self.scroll + height {
+            self.scroll = self.selected - height + 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mock_server::{MockServer, Response};
+
+    // A stand-in for the search API that answers with a single hit. Returns
+    // the server and the API base URL to point a client at.
+    async fn fake_algolia() -> (MockServer, String) {
+        let server = MockServer::start(|_| {
+            Response::json(
+                r#"{"hits":[{"objectID":"8863","title":"My YC app","author":"dhouston","points":104,"created_at_i":1175714200}],"nbHits":1}"#,
+            )
+        })
+        .await;
+        let url = format!("{}/api/v1/", server.url);
+        (server, url)
+    }
+
+    // Splits a request target into its path and decoded query parameters.
+    fn split_target(target: &str) -> (String, Vec<(String, String)>) {
+        let url = reqwest::Url::parse(&format!("http://localhost{}", target)).unwrap();
+        let params = url.query_pairs().into_owned().collect();
+        (url.path().to_string(), params)
+    }
+
+    fn param<'a>(params: &'a [(String, String)], name: &str) -> Option<&'a str> {
+        params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[test]
+    fn parses_filter_tokens_out_of_the_text() {
+        let query = SearchQuery::parse(
+            "rust  points>100 by:pg after:2024-01-31 before:2024-02-29 async site:example.com",
+            SearchKind::Stories,
+            SearchSort::Date,
+        )
+        .unwrap();
+        assert_eq!(query.text, "rust async");
+        assert_eq!(query.min_points, Some(101));
+        assert_eq!(query.author.as_deref(), Some("pg"));
+        assert_eq!(query.site.as_deref(), Some("example.com"));
+        assert_eq!(query.after, Some(1_706_659_200));
+        assert_eq!(query.before, Some(1_709_164_800 + 86_399));
+
+        let query =
+            SearchQuery::parse("points>=5", SearchKind::Comments, SearchSort::Relevance).unwrap();
+        assert_eq!(query.min_points, Some(5));
+        assert!(query.text.is_empty());
+    }
+
+    #[test]
+    fn rejects_bad_points_and_dates() {
+        let parse = |input| SearchQuery::parse(input, SearchKind::Stories, SearchSort::Relevance);
+        assert!(parse("points>many").is_err());
+        assert!(parse("points>4294967295").is_err());
+        assert!(parse("points>=4294967295").is_ok());
+        assert!(parse("after:2024-13-01").is_err());
+        assert!(parse("after:yesterday").is_err());
+    }
+
+    #[test]
+    fn checks_days_against_the_month() {
+        assert!(parse_date("2024-02-29").is_ok());
+        assert!(parse_date("2023-02-29").is_err());
+        assert!(parse_date("2024-02-31").is_err());
+        assert!(parse_date("2024-04-31").is_err());
+        assert!(parse_date("2000-02-29").is_ok());
+        assert!(parse_date("1900-02-29").is_err());
+        assert!(parse_date("2024-12-31").is_ok());
+        assert!(parse_date("2024-01-00").is_err());
+        assert!(parse_date("1969-12-31").is_err());
+    }
+
+    #[test]
+    fn formats_dates_back_the_way_they_parse() {
+        assert_eq!(parse_date("1970-01-01"), Ok(0));
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(1_175_714_200), "2007-04-04");
+        for date in ["2000-02-29", "2024-03-01", "2038-01-19", "2100-12-31"] {
+            assert_eq!(format_date(parse_date(date).unwrap()), date);
+        }
+        // Any time of day is still that day.
+        assert_eq!(
+            format_date(parse_date("2024-02-29").unwrap() + 86_399),
+            "2024-02-29"
+        );
+    }
+
+    #[tokio::test]
+    async fn searches_stories_by_relevance_with_filters() {
+        let (server, url) = fake_algolia().await;
+        let client = SearchClient::new(&url);
+        let query = SearchQuery::parse(
+            "rust by:pg points>100 after:2024-01-01",
+            SearchKind::Stories,
+            SearchSort::Relevance,
+        )
+        .unwrap();
+        let results = client.search(&query).await.unwrap();
+        assert_eq!(results.total, 1);
+        assert_eq!(results.hits[0].id(), Some(8863));
+        assert!(!results.hits[0].is_comment());
+
+        let (path, params) = split_target(server.requests()[0].target());
+        assert_eq!(path, "/api/v1/search");
+        assert_eq!(param(&params, "query"), Some("rust"));
+        assert_eq!(param(&params, "tags"), Some("story,author_pg"));
+        assert_eq!(
+            param(&params, "numericFilters"),
+            Some("created_at_i>=1704067200,points>=101")
+        );
+        assert_eq!(param(&params, "restrictSearchableAttributes"), None);
+    }
+
+    #[tokio::test]
+    async fn searches_comments_by_date() {
+        let (server, url) = fake_algolia().await;
+        let client = SearchClient::new(&url);
+        let query = SearchQuery::parse(
+            "site:example.com tls",
+            SearchKind::Comments,
+            SearchSort::Date,
+        )
+        .unwrap();
+        client.search(&query).await.unwrap();
+
+        let (path, params) = split_target(server.requests()[0].target());
+        assert_eq!(path, "/api/v1/search_by_date");
+        assert_eq!(param(&params, "tags"), Some("comment"));
+        assert_eq!(param(&params, "query"), Some("example.com tls"));
+        assert_eq!(param(&params, "restrictSearchableAttributes"), Some("url"));
+        assert_eq!(param(&params, "numericFilters"), None);
+    }
+}
@@ -0,0 +1,101 @@
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{data_dir, read_data_file, write_data_file};
+use crate::types::{Comment, Story};
+
+// A saved story, or a single comment within a story's thread, with the user's
+// tags and note. The story is stored as it was when saved so the "Saved"
+// section works without refetching anything.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub story: Story,
+    #[serde(default)]
+    pub comment: Option<Comment>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub note: String,
+    pub saved_at: u64,
+}
+
+impl Bookmark {
+    pub fn new(story: Story, comment: Option<Comment>) -> Bookmark {
+        Bookmark {
+            story,
+            comment,
+            tags: Vec::new(),
+            note: String::new(),
+            saved_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn comment_id(&self) -> Option<u32> {
+        self.comment.as_ref().map(|c| c.id)
+    }
+
+    // Parses a comma- or space-separated tag list, dropping empties and `#`s.
+    pub fn set_tags(&mut self, input: &str) {
+        self.tags = input
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .map(|t| t.trim_start_matches('#').to_lowercase())
+            .filter(|t| !t.is_empty())
+            .collect();
+    }
+}
+
+// Bookmarks persisted as JSON in the data directory, newest first.
+#[derive(Default)]
+pub struct Bookmarks {
+    pub items: Vec<Bookmark>,
+    path: Option<PathBuf>,
+}
+
+impl Bookmarks {
+    pub fn load() -> Result<Bookmarks, String> {
+        let path = data_dir().map(|dir| dir.join("bookmarks.json"));
+        let items = match &path {
+            Some(path) => read_data_file(path, "bookmarks")?,
+            None => Vec::new(),
+        };
+        Ok(Bookmarks { items, path })
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        write_data_file(self.path.as_deref(), &self.items, "bookmarks")
+    }
+
+    pub fn position(&self, story_id: u32, comment_id: Option<u32>) -> Option<usize> {
+        self.items
+            .iter()
+            .position(|b| b.story.id == story_id && b.comment_id() == comment_id)
+    }
+
+    pub fn add(&mut self, bookmark: Bookmark) -> Result<(), String> {
+        if let Some(i) = self.position(bookmark.story.id, bookmark.comment_id()) {
+            self.items.remove(i);
+        }
+        self.items.insert(0, bookmark);
+        self.save()
+    }
+
+    pub fn remove(&mut self, story_id: u32, comment_id: Option<u32>) -> Result<bool, String> {
+        match self.position(story_id, comment_id) {
+            Some(i) => {
+                self.items.remove(i);
+                self.save().map(|_| true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    // The stories shown in the "Saved" section, one per bookmark.
+    pub fn stories(&self) -> Vec<Story> {
+        self.items.iter().map(|b| b.story.clone()).collect()
+    }
+}
@@ -0,0 +1,364 @@
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+
+use crate::algolia::format_date;
+use crate::bookmarks::Bookmarks;
+use crate::error::ApiError;
+use crate::export::ExportFormat;
+use crate::hn_api::{
+    fetch_comments_to_depth, fetch_feed, fetch_user, find_story, get_claude_summary,
+    is_valid_username, parse_item_ref, summary_prompt, COMMENT_DEPTH,
+};
+use crate::types::{Section, Story};
+use crate::ui::strip_html;
+
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Browse Hacker News in the terminal",
+    args_conflicts_with_subcommands = true
+)]
+pub struct Cli {
+    // Without a subcommand the full-screen app starts, set up by the options
+    // below.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Section to start in
+    #[arg(long, value_enum)]
+    pub section: Option<SectionArg>,
+    /// Open a story's comments, by id or HN link
+    #[arg(long, value_name = "ID|URL", value_parser = parse_item_arg)]
+    pub item: Option<u32>,
+    /// Search HN for a user's stories
+    #[arg(long, value_name = "NAME")]
+    pub user: Option<String>,
+    /// Filter the story list, using the filter-bar query syntax
+    #[arg(long, value_name = "QUERY")]
+    pub search: Option<String>,
+    /// Show a plain loading box instead of the matrix rain
+    #[arg(long)]
+    pub no_animation: bool,
+    /// Append diagnostic logs to a file; set the level with HACKERTUAH_LOG
+    #[arg(long, value_name = "PATH", global = true)]
+    pub log_file: Option<PathBuf>,
+}
+
+fn parse_item_arg(input: &str) -> Result<u32, String> {
+    parse_item_ref(input).ok_or_else(|| "expected an item id or an HN item link".to_string())
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Print the stories of a section
+    List {
+        #[arg(value_enum, default_value = "top")]
+        section: SectionArg,
+        /// Number of stories
+        #[arg(short = 'n', long, default_value_t = 30)]
+        limit: usize,
+        #[arg(short, long, value_enum, default_value = "plain")]
+        format: ListFormat,
+    },
+    /// Print a story, optionally with its comments
+    Item {
+        #[arg(value_name = "ID|URL", value_parser = parse_item_arg)]
+        id: u32,
+        /// Include the comment thread
+        #[arg(short, long)]
+        comments: bool,
+        /// Levels of replies to include; 1 is top-level comments only
+        #[arg(short, long, default_value_t = COMMENT_DEPTH as u16 + 1,
+              value_parser = clap::value_parser!(u16).range(1..))]
+        depth: u16,
+        #[arg(short, long, value_enum, default_value = "markdown")]
+        format: ItemFormat,
+    },
+    /// Print a user's profile
+    User {
+        name: String,
+        #[arg(short, long, value_enum, default_value = "plain")]
+        format: UserFormat,
+    },
+    /// Summarize a story with Claude (needs CLAUDE_API_KEY)
+    Summarize {
+        #[arg(value_name = "ID|URL", value_parser = parse_item_arg)]
+        id: u32,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum SectionArg {
+    Top,
+    Ask,
+    Show,
+    Jobs,
+    Saved,
+}
+
+impl SectionArg {
+    pub fn section(&self) -> Section {
+        match self {
+            SectionArg::Top => Section::Top,
+            SectionArg::Ask => Section::Ask,
+            SectionArg::Show => Section::Show,
+            SectionArg::Jobs => Section::Jobs,
+            SectionArg::Saved => Section::Saved,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ListFormat {
+    Plain,
+    Tsv,
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ItemFormat {
+    Markdown,
+    Json,
+    Html,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum UserFormat {
+    Plain,
+    Json,
+}
+
+// Why a subcommand failed. Each kind has its own exit status so scripts can
+// tell a missing item from a network outage; usage errors exit with clap's 2.
+#[derive(Debug)]
+pub enum CliError {
+    // An argument clap accepts but the command can't use, e.g. a malformed
+    // user name.
+    Usage(String),
+    // The item or user doesn't exist.
+    NotFound(String),
+    // The item exists but isn't what the command works on, e.g. a comment
+    // where a story is needed.
+    Data(String),
+    // Missing API key, unreadable bookmarks and the like.
+    Config(String),
+    // The HN or Claude API couldn't be reached or answered with an error.
+    Fetch(String),
+    // Writing to stdout failed.
+    Output(io::Error),
+}
+
+impl From<ApiError> for CliError {
+    fn from(e: ApiError) -> Self {
+        match e {
+            ApiError::Auth(_) | ApiError::Invalid(_) => CliError::Config(e.to_string()),
+            e => CliError::Fetch(e.to_string()),
+        }
+    }
+}
+
+impl CliError {
+    // Statuses follow sysexits(3).
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            CliError::Usage(_) => 64,
+            CliError::Data(_) => 65,
+            CliError::NotFound(_) => 66,
+            CliError::Fetch(_) => 69,
+            CliError::Output(_) => 74,
+            CliError::Config(_) => 78,
+        }
+    }
+}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::NotFound(what) => write!(f, "{} not found", what),
+            CliError::Usage(e) | CliError::Data(e) | CliError::Config(e) | CliError::Fetch(e) => {
+                write!(f, "{}", e)
+            }
+            CliError::Output(e) => write!(f, "writing output: {}", e),
+        }
+    }
+}
+
+// Runs a subcommand without touching the terminal. Output goes to stdout,
+// errors to stderr. A reader that goes away early (`| head`) isn't an error.
+pub async fn run(command: Command) -> ExitCode {
+    let result = match command {
+        Command::List {
+            section,
+            limit,
+            format,
+        } => list(section.section(), limit, format).await,
+        Command::Item {
+            id,
+            comments,
+            depth,
+            format,
+        } => item(id, comments.then_some(depth as usize), format).await,
+        Command::User { name, format } => user(&name, format).await,
+        Command::Summarize { id } => summarize(id).await,
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(CliError::Output(e)) if e.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("hackertuah: {}", e);
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+fn emit(output: &str) -> Result<(), CliError> {
+    let mut stdout = io::stdout().lock();
+    stdout
+        .write_all(output.as_bytes())
+        .and_then(|()| stdout.flush())
+        .map_err(CliError::Output)
+}
+
+async fn list(section: Section, limit: usize, format: ListFormat) -> Result<(), CliError> {
+    let stories = if section == Section::Saved {
+        let bookmarks = Bookmarks::load().map_err(CliError::Config)?;
+        bookmarks
+            .items
+            .into_iter()
+            .filter(|b| b.comment.is_none())
+            .map(|b| b.story)
+            .take(limit)
+            .collect()
+    } else {
+        let page = fetch_feed(section, limit).await?;
+        // Partial results are still useful; only say what's missing.
+        if let Some(summary) = page.summary() {
+            if page.stories.is_empty() && page.failed > 0 {
+                return Err(CliError::Fetch(summary));
+            }
+            eprintln!("hackertuah: {}: {}", section.as_str(), summary);
+        }
+        page.stories
+    };
+    emit(&format_stories(&stories, format))
+}
+
+#[derive(Serialize)]
+struct ListedStory<'a> {
+    #[serde(flatten)]
+    story: &'a Story,
+    comments_url: String,
+}
+
+fn format_stories(stories: &[Story], format: ListFormat) -> String {
+    let comments_url = |id: u32| format!("https://news.ycombinator.com/item?id={}", id);
+    match format {
+        ListFormat::Plain => stories
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                format!(
+                    "{:>3}. {}\n     {} points by {} | {} comments | {}\n",
+                    i + 1,
+                    s.title,
+                    s.score,
+                    s.by,
+                    s.descendants,
+                    s.url.clone().unwrap_or_else(|| comments_url(s.id))
+                )
+            })
+            .collect(),
+        // One story per line; tabs and newlines in titles become spaces so
+        // the columns stay aligned.
+        ListFormat::Tsv => {
+            let field = |text: &str| text.replace(['\t', '\n', '\r'], " ");
+            let mut out = String::from("id\tscore\tcomments\tby\ttime\ttitle\turl\n");
+            for s in stories {
+                out.push_str(&format!(
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                    s.id,
+                    s.score,
+                    s.descendants,
+                    field(&s.by),
+                    s.time,
+                    field(&s.title),
+                    field(s.url.as_deref().unwrap_or_default())
+                ));
+            }
+            out
+        }
+        ListFormat::Json => {
+            let listed: Vec<ListedStory> = stories
+                .iter()
+                .map(|story| ListedStory {
+                    story,
+                    comments_url: comments_url(story.id),
+                })
+                .collect();
+            // Plain data; serializing it can't fail.
+            serde_json::to_string_pretty(&listed).unwrap_or_default() + "\n"
+        }
+    }
+}
+
+async fn fetch_item(id: u32) -> Result<Story, CliError> {
+    match find_story(id).await {
+        Ok(story) => story.ok_or_else(|| CliError::NotFound(format!("item {}", id))),
+        Err(ApiError::Invalid(e)) => Err(CliError::Data(e)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+async fn item(id: u32, depth: Option<usize>, format: ItemFormat) -> Result<(), CliError> {
+    let story = fetch_item(id).await?;
+    let comments = match depth {
+        Some(depth) => fetch_comments_to_depth(&story, depth - 1).await?,
+        None => Vec::new(),
+    };
+    let format = match format {
+        ItemFormat::Markdown => ExportFormat::Markdown,
+        ItemFormat::Json => ExportFormat::Json,
+        ItemFormat::Html => ExportFormat::Html,
+    };
+    emit(&format.render(&story, &comments))
+}
+
+async fn user(name: &str, format: UserFormat) -> Result<(), CliError> {
+    if !is_valid_username(name) {
+        return Err(CliError::Usage(format!(
+            "not an HN user name: {:?} (letters, digits, - and _ only)",
+            name
+        )));
+    }
+    let user = fetch_user(name)
+        .await?
+        .ok_or_else(|| CliError::NotFound(format!("user {}", name)))?;
+    let output = match format {
+        UserFormat::Plain => {
+            let mut out = format!(
+                "{}\nkarma: {}\ncreated: {}\nsubmissions: {}\n",
+                user.id,
+                user.karma,
+                format_date(user.created),
+                user.submitted.len()
+            );
+            if let Some(about) = &user.about {
+                out.push('\n');
+                out.push_str(strip_html(about).trim());
+                out.push('\n');
+            }
+            out
+        }
+        UserFormat::Json => serde_json::to_string_pretty(&user).unwrap_or_default() + "\n",
+    };
+    emit(&output)
+}
+
+async fn summarize(id: u32) -> Result<(), CliError> {
+    let story = fetch_item(id).await?;
+    let summary = get_claude_summary(&summary_prompt(&story)).await?;
+    emit(&format!("{}\n", summary.trim_end()))
+}
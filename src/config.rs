@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::filters::MuteRules;
+use crate::theme::ThemeSpec;
+
+// User configuration, read from `$HACKERTUAH_CONFIG` or
+// `<config dir>/hackertuah/config.toml`. Every field is optional so a missing
+// or partial file falls back to the built-in defaults.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    // Key overrides per mode, e.g. `[keys.normal] "ctrl+n" = "move-down"`.
+    pub keys: HashMap<String, HashMap<String, String>>,
+    // Name of a built-in theme or one defined under `[themes.<name>]`.
+    pub theme: Option<String>,
+    pub themes: HashMap<String, ThemeSpec>,
+    // Capture the mouse for clicks and scrolling. Turning it off gives the
+    // terminal's own text selection back.
+    pub mouse: bool,
+    // Domains, users and title patterns to hide, on top of those muted in
+    // the app.
+    pub mute: MuteRules,
+    pub search: SearchConfig,
+    pub account: AccountConfig,
+    pub network: NetworkConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    // Algolia HN Search API root, e.g. a local stand-in for testing.
+    pub base_url: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct AccountConfig {
+    // news.ycombinator.com root used for logging in, voting, favoriting and
+    // hiding, e.g. a local stand-in for testing.
+    pub base_url: Option<String>,
+}
+
+// How requests to HN, the search API and Claude leave the machine.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct NetworkConfig {
+    // `http://`, `https://`, `socks5://` or `socks5h://` URL, with
+    // `user:password@` if the proxy needs it. Replaces any system proxy.
+    pub proxy: Option<String>,
+    // Follow HTTP_PROXY, HTTPS_PROXY, ALL_PROXY and NO_PROXY.
+    pub system_proxy: bool,
+    // PEM files with root certificates to trust on top of the system's,
+    // e.g. a company CA that signs an intercepting proxy's certificates.
+    pub ca_certificates: Vec<PathBuf>,
+    pub user_agent: Option<String>,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        NetworkConfig {
+            proxy: None,
+            system_proxy: true,
+            ca_certificates: Vec::new(),
+            user_agent: None,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            keys: HashMap::new(),
+            theme: None,
+            themes: HashMap::new(),
+            mouse: true,
+            mute: MuteRules::default(),
+            search: SearchConfig::default(),
+            account: AccountConfig::default(),
+            network: NetworkConfig::default(),
+        }
+    }
+}
+
+// Directory for state the app persists between runs (bookmarks, history, ...):
+// `$HACKERTUAH_DATA_DIR` or `<data dir>/hackertuah`.
+pub fn data_dir() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("HACKERTUAH_DATA_DIR") {
+        return Some(PathBuf::from(path));
+    }
+    dirs::data_dir().map(|dir| dir.join("hackertuah"))
+}
+
+// Reads a JSON state file, treating a missing file as empty state.
+pub fn read_data_file<T: DeserializeOwned + Default>(path: &Path, what: &str) -> Result<T, String> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| format!("Invalid {} file {}: {}", what, path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(format!("Failed to read {} {}: {}", what, path.display(), e)),
+    }
+}
+
+pub fn write_data_file<T: Serialize>(
+    path: Option<&Path>,
+    value: &T,
+    what: &str,
+) -> Result<(), String> {
+    let Some(path) = path else {
+        return Err(format!("No data directory available to save {}", what));
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    std::fs::write(path, json)
+        .map_err(|e| format!("Failed to save {} {}: {}", what, path.display(), e))
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        if let Ok(path) = std::env::var("HACKERTUAH_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+        dirs::config_dir().map(|dir| dir.join("hackertuah").join("config.toml"))
+    }
+
+    pub fn load() -> Result<Config, String> {
+        let path = match Self::path() {
+            Some(path) => path,
+            None => return Ok(Config::default()),
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str(&contents)
+                .map_err(|e| format!("Invalid config {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(format!("Failed to read config {}: {}", path.display(), e)),
+        }
+    }
+}
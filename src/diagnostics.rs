@@ -0,0 +1,139 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use tracing_subscriber::EnvFilter;
+
+// Log filter in `tracing` syntax, e.g. `debug` or `hackertuah=debug,warn`.
+pub const LOG_ENV: &str = "HACKERTUAH_LOG";
+const DEFAULT_LOG_LEVEL: &str = "info";
+
+// How many finished requests the debug overlay remembers.
+const RECENT_REQUESTS: usize = 50;
+
+// Appends log events to `path`. Without a log file events are dropped; the
+// debug overlay works either way.
+pub fn init_log_file(path: &Path) -> Result<(), String> {
+    let filter = match std::env::var(LOG_ENV) {
+        Ok(spec) => EnvFilter::try_new(&spec).map_err(|e| format!("{}: {}", LOG_ENV, e))?,
+        Err(_) => EnvFilter::new(DEFAULT_LOG_LEVEL),
+    };
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("can't open log file {}: {}", path.display(), e))?;
+    tracing_subscriber::fmt()
+        .with_writer(Mutex::new(file))
+        .with_ansi(false)
+        .with_env_filter(filter)
+        .try_init()
+        .map_err(|e| e.to_string())
+}
+
+// One finished HTTP attempt.
+#[derive(Clone)]
+pub struct RequestRecord {
+    pub method: String,
+    pub url: String,
+    // `None` when no response came back.
+    pub status: Option<u16>,
+    pub latency: Duration,
+    pub error: Option<String>,
+}
+
+// A running task as shown in the overlay.
+pub struct TaskInfo {
+    pub name: String,
+    pub elapsed: Duration,
+}
+
+// What the debug overlay shows, copied out so drawing doesn't hold the lock.
+pub struct Snapshot {
+    // Most recent first.
+    pub requests: Vec<RequestRecord>,
+    pub tasks: Vec<TaskInfo>,
+    pub errors: Vec<(String, usize)>,
+    pub total_requests: usize,
+    pub cache_hits: usize,
+}
+
+struct State {
+    requests: VecDeque<RequestRecord>,
+    tasks: Vec<(u64, String, Instant)>,
+    next_task: u64,
+    errors: BTreeMap<String, usize>,
+    total_requests: usize,
+    cache_hits: usize,
+}
+
+static STATE: Mutex<State> = Mutex::new(State {
+    requests: VecDeque::new(),
+    tasks: Vec::new(),
+    next_task: 0,
+    errors: BTreeMap::new(),
+    total_requests: 0,
+    cache_hits: 0,
+});
+
+// A panic elsewhere mustn't take the overlay down with it.
+fn state() -> MutexGuard<'static, State> {
+    STATE.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+pub fn record_request(record: RequestRecord) {
+    let mut state = state();
+    state.total_requests += 1;
+    if state.requests.len() == RECENT_REQUESTS {
+        state.requests.pop_back();
+    }
+    state.requests.push_front(record);
+}
+
+// Counts a failure under `kind`, e.g. "timeout" or "HTTP 503".
+pub fn record_error(kind: impl Into<String>) {
+    *state().errors.entry(kind.into()).or_default() += 1;
+}
+
+pub fn record_cache_hit() {
+    state().cache_hits += 1;
+}
+
+// Marks a task as in flight until the returned guard is dropped.
+pub fn start_task(name: impl Into<String>) -> TaskGuard {
+    let mut state = state();
+    let id = state.next_task;
+    state.next_task += 1;
+    state.tasks.push((id, name.into(), Instant::now()));
+    TaskGuard { id }
+}
+
+pub struct TaskGuard {
+    id: u64,
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        state().tasks.retain(|(id, _, _)| *id != self.id);
+    }
+}
+
+pub fn snapshot() -> Snapshot {
+    let state = state();
+    Snapshot {
+        requests: state.requests.iter().cloned().collect(),
+        tasks: state
+            .tasks
+            .iter()
+            .map(|(_, name, started)| TaskInfo {
+                name: name.clone(),
+                elapsed: started.elapsed(),
+            })
+            .collect(),
+        errors: state.errors.iter().map(|(k, n)| (k.clone(), *n)).collect(),
+        total_requests: state.total_requests,
+        cache_hits: state.cache_hits,
+    }
+}
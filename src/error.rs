@@ -0,0 +1,165 @@
+use std::fmt;
+use std::io;
+
+use reqwest::StatusCode;
+
+// Why a call to the HN, search or Claude API failed. Each kind is worded for
+// the user, and the ones that may clear up by themselves can be retried.
+#[derive(Debug)]
+pub enum ApiError {
+    // The server couldn't be reached or the connection dropped.
+    Network {
+        host: String,
+        source: reqwest::Error,
+    },
+    // A request, or a whole load, took too long. Holds what timed out.
+    Timeout(String),
+    // The server answered with an error status.
+    Status {
+        host: String,
+        status: StatusCode,
+    },
+    // The response didn't have the shape the API documents.
+    Decode(String),
+    // A missing or rejected API key.
+    Auth(String),
+    // The Claude API turned the request down, with its own explanation.
+    Llm {
+        status: StatusCode,
+        message: String,
+    },
+    // A request that can't be made at all, e.g. for a section without a feed.
+    Invalid(String),
+}
+
+impl ApiError {
+    // Whether trying again later might work.
+    pub fn is_retryable(&self) -> bool {
+        let transient = |status: &StatusCode| {
+            *status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+        };
+        match self {
+            ApiError::Network { .. } | ApiError::Timeout(_) => true,
+            ApiError::Status { status, .. } | ApiError::Llm { status, .. } => transient(status),
+            ApiError::Decode(_) | ApiError::Auth(_) | ApiError::Invalid(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Network { host, .. } => {
+                write!(f, "can't reach {}, check your connection", host)
+            }
+            ApiError::Timeout(what) => write!(f, "{} timed out", what),
+            ApiError::Status { host, status } if *status == StatusCode::TOO_MANY_REQUESTS => {
+                write!(f, "{} is rate limiting requests", host)
+            }
+            ApiError::Status { host, status } => write!(f, "{} answered {}", host, status),
+            ApiError::Decode(detail) => {
+                write!(
+                    f,
+                    "unexpected response ({}), the API may have changed",
+                    detail
+                )
+            }
+            ApiError::Auth(message) | ApiError::Invalid(message) => write!(f, "{}", message),
+            ApiError::Llm { message, .. } => write!(f, "Claude API error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ApiError::Network { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(e: reqwest::Error) -> Self {
+        let host = e
+            .url()
+            .and_then(|url| url.host_str())
+            .unwrap_or("the server")
+            .to_string();
+        if e.is_timeout() {
+            ApiError::Timeout(format!("request to {}", host))
+        } else if let Some(status) = e.status() {
+            ApiError::Status { host, status }
+        } else if e.is_decode() {
+            // The useful part, e.g. serde's "missing field `title`", is the
+            // source.
+            let detail = std::error::Error::source(&e).map_or(e.to_string(), |s| s.to_string());
+            ApiError::Decode(format!("{}: {}", host, detail))
+        } else {
+            ApiError::Network { host, source: e }
+        }
+    }
+}
+
+impl From<serde_json::Error> for ApiError {
+    fn from(e: serde_json::Error) -> Self {
+        ApiError::Decode(e.to_string())
+    }
+}
+
+// What can go wrong while the app loads something behind the loading screen.
+#[derive(Debug)]
+pub enum AppError {
+    Api(ApiError),
+    // Drawing or reading input failed.
+    Terminal(io::Error),
+    // The background task doing the fetch panicked or was cancelled.
+    Task(tokio::task::JoinError),
+}
+
+impl AppError {
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            AppError::Api(e) => e.is_retryable(),
+            AppError::Terminal(_) | AppError::Task(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Api(e) => write!(f, "{}", e),
+            AppError::Terminal(e) => write!(f, "terminal error: {}", e),
+            AppError::Task(e) => write!(f, "background task failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AppError::Api(e) => Some(e),
+            AppError::Terminal(e) => Some(e),
+            AppError::Task(e) => Some(e),
+        }
+    }
+}
+
+impl From<ApiError> for AppError {
+    fn from(e: ApiError) -> Self {
+        AppError::Api(e)
+    }
+}
+
+impl From<io::Error> for AppError {
+    fn from(e: io::Error) -> Self {
+        AppError::Terminal(e)
+    }
+}
+
+impl From<tokio::task::JoinError> for AppError {
+    fn from(e: tokio::task::JoinError) -> Self {
+        AppError::Task(e)
+    }
+}
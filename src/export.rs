@@ -0,0 +1,320 @@
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::algolia::format_date;
+use crate::types::{FlatComment, Story};
+use crate::ui::strip_html;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+    Html,
+}
+
+// Where an export goes: a file, or stdout once the TUI has exited.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ExportTarget {
+    // An existing file is only replaced with `overwrite`.
+    File { path: PathBuf, overwrite: bool },
+    Stdout,
+}
+
+impl ExportFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "Markdown",
+            ExportFormat::Json => "JSON",
+            ExportFormat::Html => "HTML",
+        }
+    }
+
+    // Parses what was typed in the export prompt: an optional `md:`, `json:`
+    // or `html:` prefix, then a path or `-` for stdout, with a trailing `!` to
+    // replace an existing file. Without a prefix the format follows the file
+    // extension, defaulting to Markdown.
+    pub fn parse_target(input: &str) -> Result<(ExportFormat, ExportTarget), String> {
+        let input = input.trim();
+        // Anything else before a colon is part of the path.
+        let prefixed = input.split_once(':').and_then(|(prefix, path)| {
+            let format = match prefix {
+                "md" | "markdown" => ExportFormat::Markdown,
+                "json" => ExportFormat::Json,
+                "html" => ExportFormat::Html,
+                _ => return None,
+            };
+            Some((format, path.trim()))
+        });
+        let (format, path) = prefixed.unwrap_or_else(|| {
+            let extension = input
+                .trim_end_matches('!')
+                .rsplit_once('.')
+                .map(|(_, ext)| ext.to_lowercase());
+            let format = match extension.as_deref() {
+                Some("json") => ExportFormat::Json,
+                Some("html" | "htm") => ExportFormat::Html,
+                _ => ExportFormat::Markdown,
+            };
+            (format, input)
+        });
+        let (path, overwrite) = match path.strip_suffix('!') {
+            Some(path) => (path.trim_end(), true),
+            None => (path, false),
+        };
+        let target = match path {
+            "" => return Err("no export path given".to_string()),
+            "-" => ExportTarget::Stdout,
+            path => ExportTarget::File {
+                path: expand_home(path),
+                overwrite,
+            },
+        };
+        Ok((format, target))
+    }
+
+    pub fn render(&self, story: &Story, comments: &[FlatComment]) -> String {
+        match self {
+            ExportFormat::Markdown => to_markdown(story, comments),
+            ExportFormat::Json => to_json(story, comments),
+            ExportFormat::Html => to_html(story, comments),
+        }
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn item_url(id: u32) -> String {
+    format!("https://news.ycombinator.com/item?id={}", id)
+}
+
+fn comment_author(fc: &FlatComment) -> &str {
+    if fc.comment.by.is_empty() {
+        "[deleted]"
+    } else {
+        &fc.comment.by
+    }
+}
+
+fn to_markdown(story: &Story, comments: &[FlatComment]) -> String {
+    let mut out = match &story.url {
+        Some(url) => format!("# [{}]({})\n\n", story.title, url),
+        None => format!("# {}\n\n", story.title),
+    };
+    out.push_str(&format!(
+        "{} points by {} on {} | [{} comments]({})\n",
+        story.score,
+        story.by,
+        format_date(story.time),
+        story.descendants,
+        item_url(story.id)
+    ));
+    if let Some(text) = &story.text {
+        out.push('\n');
+        out.push_str(strip_html(text).trim());
+        out.push('\n');
+    }
+
+    // Each reply is quoted one level deeper than its parent.
+    for fc in comments {
+        let quote = ">".repeat(fc.depth + 1);
+        out.push('\n');
+        out.push_str(&format!(
+            "{} **{}** on {} ([link]({}))\n{}\n",
+            quote,
+            comment_author(fc),
+            format_date(fc.comment.time),
+            item_url(fc.comment.id),
+            quote
+        ));
+        let text = fc
+            .comment
+            .text
+            .as_deref()
+            .map(strip_html)
+            .unwrap_or_default();
+        for line in text.trim().lines() {
+            if line.is_empty() {
+                out.push_str(&format!("{}\n", quote));
+            } else {
+                out.push_str(&format!("{} {}\n", quote, line));
+            }
+        }
+    }
+    out
+}
+
+#[derive(Serialize)]
+struct ExportedThread<'a> {
+    story: &'a Story,
+    comments: Vec<ExportedComment<'a>>,
+}
+
+#[derive(Serialize)]
+struct ExportedComment<'a> {
+    id: u32,
+    parent: u32,
+    depth: usize,
+    by: &'a str,
+    time: u64,
+    // As returned by the HN API, i.e. HTML.
+    text: Option<&'a str>,
+    deleted: bool,
+    dead: bool,
+}
+
+fn to_json(story: &Story, comments: &[FlatComment]) -> String {
+    let thread = ExportedThread {
+        story,
+        comments: comments
+            .iter()
+            .map(|fc| ExportedComment {
+                id: fc.comment.id,
+                parent: fc.comment.parent.unwrap_or(story.id),
+                depth: fc.depth,
+                by: &fc.comment.by,
+                time: fc.comment.time,
+                text: fc.comment.text.as_deref(),
+                deleted: fc.comment.deleted,
+                dead: fc.comment.dead,
+            })
+            .collect(),
+    };
+    // Plain data; serializing it can't fail.
+    serde_json::to_string_pretty(&thread).unwrap_or_default() + "\n"
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const HTML_STYLE: &str = "body { font-family: Verdana, Geneva, sans-serif; max-width: 50em; \
+margin: 2em auto; padding: 0 1em; color: #222; background: #f6f6ef; }
+h1 { font-size: 1.3em; } h1 a { color: #000; text-decoration: none; }
+.meta { color: #828282; font-size: 0.85em; } .meta a { color: #828282; }
+.comment { border-left: 2px solid #ddd; padding: 0.2em 0 0.2em 0.8em; margin: 0.8em 0; }
+.comment p { margin: 0.4em 0; } pre { white-space: pre-wrap; }";
+
+// A single self-contained page. Comment bodies are HN's own (already
+// sanitized) HTML; everything else is escaped.
+fn to_html(story: &Story, comments: &[FlatComment]) -> String {
+    let title = escape_html(&story.title);
+    let heading = match &story.url {
+        Some(url) => format!("<a href=\"{}\">{}</a>", escape_html(url), title),
+        None => title.clone(),
+    };
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>\n{}\n</style>\n</head>\n<body>\n<h1>{}</h1>\n\
+         <p class=\"meta\">{} points by {} on {} | <a href=\"{}\">{} comments</a></p>\n",
+        title,
+        HTML_STYLE,
+        heading,
+        story.score,
+        escape_html(&story.by),
+        format_date(story.time),
+        item_url(story.id),
+        story.descendants
+    );
+    if let Some(text) = &story.text {
+        out.push_str(&format!("<div class=\"text\"><p>{}</p></div>\n", text));
+    }
+    for fc in comments {
+        out.push_str(&format!(
+            "<div class=\"comment\" id=\"{}\" style=\"margin-left: {}em\">\n\
+             <p class=\"meta\">{} on <a href=\"{}\">{}</a></p>\n<p>{}</p>\n</div>\n",
+            fc.comment.id,
+            fc.depth * 2,
+            escape_html(comment_author(fc)),
+            item_url(fc.comment.id),
+            format_date(fc.comment.time),
+            fc.comment.text.as_deref().unwrap_or_default()
+        ));
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, overwrite: bool) -> ExportTarget {
+        ExportTarget::File {
+            path: PathBuf::from(path),
+            overwrite,
+        }
+    }
+
+    #[test]
+    fn picks_the_format_from_a_prefix_or_the_extension() {
+        let parse = |input| ExportFormat::parse_target(input).unwrap();
+        assert_eq!(
+            parse("json:thread.txt"),
+            (ExportFormat::Json, file("thread.txt", false))
+        );
+        assert_eq!(parse("html:-"), (ExportFormat::Html, ExportTarget::Stdout));
+        assert_eq!(
+            parse("thread.HTML"),
+            (ExportFormat::Html, file("thread.HTML", false))
+        );
+        assert_eq!(parse("-"), (ExportFormat::Markdown, ExportTarget::Stdout));
+        assert!(ExportFormat::parse_target("json:").is_err());
+    }
+
+    #[test]
+    fn keeps_colons_that_are_not_a_format_in_the_path() {
+        assert_eq!(
+            ExportFormat::parse_target("notes:x.json").unwrap(),
+            (ExportFormat::Json, file("notes:x.json", false))
+        );
+        assert_eq!(
+            ExportFormat::parse_target("C:/exports/x.md").unwrap(),
+            (ExportFormat::Markdown, file("C:/exports/x.md", false))
+        );
+    }
+
+    #[test]
+    fn a_trailing_bang_allows_overwriting() {
+        assert_eq!(
+            ExportFormat::parse_target("thread.json!").unwrap(),
+            (ExportFormat::Json, file("thread.json", true))
+        );
+        assert_eq!(
+            ExportFormat::parse_target("md:out.txt !").unwrap(),
+            (ExportFormat::Markdown, file("out.txt", true))
+        );
+    }
+
+    #[test]
+    fn json_uses_each_comments_own_parent() {
+        let story: Story = serde_json::from_value(serde_json::json!({
+            "id": 1, "title": "Story", "by": "a", "time": 0, "score": 1
+        }))
+        .unwrap();
+        let comment = |id, parent, depth| FlatComment {
+            comment: serde_json::from_value(serde_json::json!({"id": id, "parent": parent}))
+                .unwrap(),
+            depth,
+        };
+        // 4 was spliced in below 2 without its siblings, so depth alone
+        // would make it a reply to 3.
+        let comments = [comment(2, 1, 0), comment(3, 2, 1), comment(4, 9, 2)];
+        let json: serde_json::Value = serde_json::from_str(&to_json(&story, &comments)).unwrap();
+        let parents: Vec<_> = json["comments"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["parent"].as_u64().unwrap())
+            .collect();
+        assert_eq!(parents, vec![1, 2, 9]);
+    }
+}
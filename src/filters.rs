@@ -0,0 +1,154 @@
+use std::path::PathBuf;
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{data_dir, read_data_file, write_data_file};
+use crate::types::Story;
+
+// Things to hide: link domains (subdomains included), users, and
+// case-insensitive regexes matched against story titles.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MuteRules {
+    pub domains: Vec<String>,
+    pub users: Vec<String>,
+    pub keywords: Vec<String>,
+}
+
+// Mute rules from the `[mute]` config table combined with the ones added in
+// the app, which are persisted separately so the config file is never
+// rewritten.
+#[derive(Default)]
+pub struct Filters {
+    config: MuteRules,
+    local: MuteRules,
+    patterns: Vec<Regex>,
+    path: Option<PathBuf>,
+}
+
+fn compile(pattern: &str) -> Result<Regex, String> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| {
+            // Parse errors span several lines; the last one says what's wrong.
+            let message = e.to_string();
+            let reason = message.lines().last().unwrap_or_default();
+            let reason = reason.strip_prefix("error: ").unwrap_or(reason);
+            format!("invalid mute pattern '{}': {}", pattern, reason)
+        })
+}
+
+fn normalize_domain(domain: &str) -> String {
+    let domain = domain.trim().to_lowercase();
+    domain.strip_prefix("www.").unwrap_or(&domain).to_string()
+}
+
+impl Filters {
+    // Loads the in-app rules and compiles every pattern. Invalid patterns are
+    // skipped and reported.
+    pub fn load(config: &MuteRules) -> (Filters, Vec<String>) {
+        let mut errors = Vec::new();
+        let path = data_dir().map(|dir| dir.join("mutes.json"));
+        let local = match &path {
+            Some(path) => read_data_file(path, "mute rules").unwrap_or_else(|e| {
+                errors.push(e);
+                MuteRules::default()
+            }),
+            None => MuteRules::default(),
+        };
+        let mut filters = Filters {
+            config: config.clone(),
+            local,
+            patterns: Vec::new(),
+            path,
+        };
+        errors.extend(filters.compile_patterns());
+        (filters, errors)
+    }
+
+    fn compile_patterns(&mut self) -> Vec<String> {
+        let mut errors = Vec::new();
+        self.patterns = self
+            .rules()
+            .flat_map(|rules| rules.keywords.iter())
+            .filter_map(|pattern| compile(pattern).map_err(|e| errors.push(e)).ok())
+            .collect();
+        errors
+    }
+
+    fn rules(&self) -> impl Iterator<Item = &MuteRules> {
+        [&self.config, &self.local].into_iter()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        write_data_file(self.path.as_deref(), &self.local, "mute rules")
+    }
+
+    pub fn hides_domain(&self, domain: &str) -> bool {
+        self.rules().flat_map(|r| r.domains.iter()).any(|muted| {
+            let muted = normalize_domain(muted);
+            domain == muted
+                || domain
+                    .strip_suffix(&muted)
+                    .is_some_and(|sub| sub.ends_with('.'))
+        })
+    }
+
+    pub fn hides_user(&self, user: &str) -> bool {
+        self.rules()
+            .flat_map(|r| r.users.iter())
+            .any(|muted| muted.eq_ignore_ascii_case(user))
+    }
+
+    pub fn hides_story(&self, story: &Story) -> bool {
+        self.hides_user(&story.by)
+            || story.domain().is_some_and(|d| self.hides_domain(&d))
+            || self.patterns.iter().any(|p| p.is_match(&story.title))
+    }
+
+    // Mutes `domain`, or unmutes it if it was muted in the app. Returns
+    // whether it is muted afterwards. Rules from the config file are left
+    // alone.
+    pub fn toggle_domain(&mut self, domain: &str) -> Result<bool, String> {
+        let domain = normalize_domain(domain);
+        let is_same = |d: &str| normalize_domain(d) == domain;
+        if self.config.domains.iter().any(|d| is_same(d)) {
+            return Err(format!("{} is muted in the config file", domain));
+        }
+        let muted = toggle(&mut self.local.domains, &domain, is_same);
+        self.save().map(|_| muted)
+    }
+
+    pub fn toggle_user(&mut self, user: &str) -> Result<bool, String> {
+        let is_same = |u: &str| u.eq_ignore_ascii_case(user);
+        if self.config.users.iter().any(|u| is_same(u)) {
+            return Err(format!("{} is muted in the config file", user));
+        }
+        let muted = toggle(&mut self.local.users, user, is_same);
+        self.save().map(|_| muted)
+    }
+
+    pub fn add_keyword(&mut self, pattern: &str) -> Result<(), String> {
+        let regex = compile(pattern)?;
+        if !self.local.keywords.iter().any(|k| k == pattern) {
+            self.local.keywords.push(pattern.to_string());
+            self.patterns.push(regex);
+        }
+        self.save()
+    }
+}
+
+// Removes the entries matching `is_same` from `list`, or pushes `value` if there
+// were none. Returns whether `value` is in the list afterwards.
+fn toggle(list: &mut Vec<String>, value: &str, is_same: impl Fn(&str) -> bool) -> bool {
+    let before = list.len();
+    list.retain(|v| !is_same(v));
+    if list.len() == before {
+        list.push(value.to_string());
+        true
+    } else {
+        false
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{data_dir, read_data_file, write_data_file};
+use crate::types::Story;
+
+// What we know about one story from earlier visits. Times are Unix seconds.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Visit {
+    pub article_opened_at: Option<u64>,
+    pub thread_opened_at: Option<u64>,
+    // `descendants` of the story when its thread was last opened.
+    pub comment_count: Option<u32>,
+    // Ids of every comment loaded in the comments view so far; `None` until
+    // the thread has been read in the app.
+    pub seen_comments: Option<HashSet<u32>>,
+}
+
+// Stories remembered at most; the least recently opened are forgotten first,
+// so seen-comment sets don't pile up forever.
+const MAX_VISITS: usize = 2000;
+
+impl Visit {
+    fn last_opened_at(&self) -> u64 {
+        self.article_opened_at
+            .max(self.thread_opened_at)
+            .unwrap_or_default()
+    }
+}
+
+// Per-story read history, persisted as JSON in the data directory.
+#[derive(Default)]
+pub struct History {
+    pub visits: HashMap<u32, Visit>,
+    path: Option<PathBuf>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+impl History {
+    pub fn load() -> Result<History, String> {
+        let path = data_dir().map(|dir| dir.join("history.json"));
+        let visits = match &path {
+            Some(path) => read_data_file(path, "history")?,
+            None => HashMap::new(),
+        };
+        Ok(History { visits, path })
+    }
+
+    pub fn save(&mut self) -> Result<(), String> {
+        self.prune();
+        write_data_file(self.path.as_deref(), &self.visits, "history")
+    }
+
+    // Drops the oldest visits beyond `MAX_VISITS`.
+    fn prune(&mut self) {
+        let Some(excess) = self.visits.len().checked_sub(MAX_VISITS) else {
+            return;
+        };
+        let mut by_age: Vec<(u64, u32)> = self
+            .visits
+            .iter()
+            .map(|(&id, visit)| (visit.last_opened_at(), id))
+            .collect();
+        by_age.sort_unstable();
+        for (_, id) in by_age.into_iter().take(excess) {
+            self.visits.remove(&id);
+        }
+    }
+
+    pub fn is_visited(&self, story_id: u32) -> bool {
+        self.visits.contains_key(&story_id)
+    }
+
+    pub fn mark_article_opened(&mut self, story_id: u32) -> Result<(), String> {
+        self.visits.entry(story_id).or_default().article_opened_at = Some(now());
+        self.save()
+    }
+
+    pub fn mark_thread_opened(&mut self, story: &Story) -> Result<(), String> {
+        let visit = self.visits.entry(story.id).or_default();
+        visit.thread_opened_at = Some(now());
+        visit.comment_count = Some(story.descendants);
+        self.save()
+    }
+
+    // Adds `ids` to the comments seen in `story_id`'s thread and returns the
+    // ones that weren't there on the previous visit. Nothing counts as new the
+    // first time a thread is read. Saved with the next `mark_*` call.
+    pub fn record_comments(
+        &mut self,
+        story_id: u32,
+        ids: impl IntoIterator<Item = u32>,
+    ) -> HashSet<u32> {
+        let visit = self.visits.entry(story_id).or_default();
+        let first_visit = visit.seen_comments.is_none();
+        let seen = visit.seen_comments.get_or_insert_with(HashSet::new);
+        let new: HashSet<u32> = ids.into_iter().filter(|&id| seen.insert(id)).collect();
+        if first_visit {
+            HashSet::new()
+        } else {
+            new
+        }
+    }
+
+    // How many comments the thread gained since it was last opened, if any.
+    pub fn new_comments(&self, story: &Story) -> Option<u32> {
+        let seen = self.visits.get(&story.id)?.comment_count?;
+        story.descendants.checked_sub(seen).filter(|&n| n > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forgets_the_least_recently_opened_stories() {
+        let mut history = History::default();
+        for id in 0..MAX_VISITS as u32 + 5 {
+            let visit = history.visits.entry(id).or_default();
+            visit.thread_opened_at = Some(1_000 + u64::from(id));
+            visit.seen_comments = Some(HashSet::from([id]));
+        }
+        // An old story whose article was opened just now stays.
+        history.visits.get_mut(&0).unwrap().article_opened_at = Some(now());
+        history.prune();
+
+        assert_eq!(history.visits.len(), MAX_VISITS);
+        assert!(history.is_visited(0));
+        assert!((1..=5).all(|id| !history.is_visited(id)));
+        assert!(history.is_visited(6));
+    }
+}
@@ -1,56 +1,293 @@
-use std::error::Error;
+use std::time::{Duration, Instant};
 
-use crate::types::{ClaudeRequest, Comment, FlatComment, Message, Section, Story};
+use reqwest::StatusCode;
 
-pub async fn fetch_stories(section: Section) -> Result<Vec<Story>, Box<dyn Error + Send + Sync>> {
-    let client = reqwest::Client::new();
+use crate::diagnostics;
+use crate::error::ApiError;
+use crate::http::{self, HttpClient};
+use crate::types::{
+    ClaudeRequest, Comment, FlatComment, Message, PollOption, Section, Story, User,
+};
+use crate::ui::strip_html;
 
-    let ids: Vec<u32> = client
-        .get(section.get_api_url())
-        .send()
-        .await?
-        .json()
-        .await?;
+// How many stories of a feed are loaded, and how deep threads are followed.
+pub const FEED_LIMIT: usize = 100;
+pub const COMMENT_DEPTH: usize = 4;
 
-    let mut stories = Vec::new();
-    for id in ids.iter().take(100) {
-        let story: Story = client
-            .get(format!(
-                "https://hacker-news.firebaseio.com/v0/item/{}.json",
-                id
-            ))
-            .send()
-            .await?
-            .json()
-            .await?;
-        stories.push(story);
+// Claude can take a while to write a summary.
+const SUMMARY_TIMEOUT: Duration = Duration::from_secs(60);
+const CLAUDE_API_URL: &str = "https://api.anthropic.com/v1/messages";
+// The Messages API version the request and reply shapes follow.
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+fn item_api_url(id: u32) -> String {
+    format!("https://hacker-news.firebaseio.com/v0/item/{}.json", id)
+}
+
+// The stories of a feed that could be loaded, and what happened to the rest.
+pub struct FeedPage {
+    pub stories: Vec<Story>,
+    // Items asked for, i.e. the feed's ids up to the limit.
+    pub requested: usize,
+    // Items that couldn't be fetched or parsed.
+    pub failed: usize,
+    // Deleted or dead items, left out on purpose.
+    pub skipped: usize,
+}
+
+impl FeedPage {
+    // E.g. "98/100 loaded, 2 failed", when anything was left out.
+    pub fn summary(&self) -> Option<String> {
+        if self.failed == 0 && self.skipped == 0 {
+            return None;
+        }
+        let mut summary = format!("{}/{} loaded", self.stories.len(), self.requested);
+        if self.failed > 0 {
+            summary.push_str(&format!(", {} failed", self.failed));
+        }
+        if self.skipped > 0 {
+            summary.push_str(&format!(", {} deleted", self.skipped));
+        }
+        Some(summary)
     }
+}
+
+pub async fn fetch_stories(section: Section) -> Result<FeedPage, ApiError> {
+    fetch_feed(section, FEED_LIMIT).await
+}
+
+// The first `limit` stories of a feed, in feed order. Only the feed itself
+// failing is an error; items that fail are counted and left out.
+pub async fn fetch_feed(section: Section, limit: usize) -> Result<FeedPage, ApiError> {
+    let _task = diagnostics::start_task(format!("{} feed", section.as_str()));
+    let client = http::client();
+
+    let url = section
+        .get_api_url()
+        .ok_or_else(|| ApiError::Invalid(format!("{} is not an HN feed", section.as_str())))?;
+    let ids: Vec<u32> = client.get_json(&url).await?;
 
-    Ok(stories)
+    let mut page = FeedPage {
+        stories: Vec::new(),
+        requested: ids.len().min(limit),
+        failed: 0,
+        skipped: 0,
+    };
+    for id in ids.iter().take(limit) {
+        match fetch_feed_item(client, *id).await {
+            Ok(Some(story)) => page.stories.push(story),
+            Ok(None) => page.skipped += 1,
+            Err(e) => {
+                tracing::warn!(id, error = %e, "skipping feed item");
+                page.failed += 1;
+            }
+        }
+    }
+    tracing::info!(
+        section = section.as_str(),
+        loaded = page.stories.len(),
+        failed = page.failed,
+        skipped = page.skipped,
+        "feed loaded"
+    );
+
+    Ok(page)
+}
+
+// A feed entry, or `None` if it's been deleted or killed since the feed was
+// built. Those come back as `null` or without most of their fields.
+async fn fetch_feed_item(client: &HttpClient, id: u32) -> Result<Option<Story>, ApiError> {
+    let item: serde_json::Value = client.get_json(&item_api_url(id)).await?;
+    if item.is_null() || item["deleted"] == true || item["dead"] == true {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_value(item)?))
+}
+
+// What a pasted id or HN link points at.
+#[derive(Debug, PartialEq, Eq)]
+pub enum HnLink {
+    Item(u32),
+    User(String),
+    // `from?site=`: stories linking to a domain.
+    Site(String),
+}
+
+impl HnLink {
+    // Parses a bare item id or an HN link such as
+    // `https://news.ycombinator.com/item?id=8863`, `user?id=pg` or
+    // `from?site=github.com`.
+    pub fn parse(input: &str) -> Option<HnLink> {
+        let input = input.trim();
+        if let Ok(id) = input.parse() {
+            return Some(HnLink::Item(id));
+        }
+        let (page, query) = input.rsplit('/').next()?.split_once('?')?;
+        let param = |name: &str| {
+            query
+                .split(['&', '#'])
+                .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+                .filter(|value| !value.is_empty())
+        };
+        match page {
+            "item" => param("id")?.parse().ok().map(HnLink::Item),
+            "user" | "submitted" | "threads" => param("id").map(|id| HnLink::User(id.to_string())),
+            "from" => param("site").map(|site| HnLink::Site(site.to_string())),
+            _ => None,
+        }
+    }
+}
+
+// Item id from a bare id or an HN item link.
+pub fn parse_item_ref(input: &str) -> Option<u32> {
+    match HnLink::parse(input)? {
+        HnLink::Item(id) => Some(id),
+        _ => None,
+    }
+}
+
+// An item id resolved for display: a story, or a comment along with the story
+// it belongs to.
+pub enum ItemTarget {
+    Story(Story),
+    // `thread` runs from the top-level comment down to the comment itself.
+    Comment { story: Story, thread: Vec<Comment> },
+}
+
+// Fetches an item, following a comment's parents up to its story. `None` if
+// the id doesn't exist.
+pub async fn resolve_item(id: u32) -> Result<Option<ItemTarget>, ApiError> {
+    let _task = diagnostics::start_task(format!("item {}", id));
+    let mut thread = Vec::new();
+    let mut next = id;
+    loop {
+        let item: serde_json::Value = http::client().get_json(&item_api_url(next)).await?;
+        if item.is_null() {
+            if thread.is_empty() {
+                return Ok(None);
+            }
+            return Err(ApiError::Decode(format!("parent item {} not found", next)));
+        }
+        if item["type"] != "comment" {
+            let story: Story = serde_json::from_value(item)?;
+            thread.reverse();
+            return Ok(Some(if thread.is_empty() {
+                ItemTarget::Story(story)
+            } else {
+                ItemTarget::Comment { story, thread }
+            }));
+        }
+        let comment: Comment = serde_json::from_value(item)?;
+        next = comment
+            .parent
+            .ok_or_else(|| ApiError::Decode(format!("comment {} has no parent", comment.id)))?;
+        thread.push(comment);
+    }
 }
 
-pub async fn fetch_comments(
+// A story by id, or `None` when the id doesn't exist: the API answers
+// unknown ids with `null`.
+pub async fn find_story(id: u32) -> Result<Option<Story>, ApiError> {
+    let item: serde_json::Value = http::client().get_json(&item_api_url(id)).await?;
+    if item.is_null() {
+        return Ok(None);
+    }
+    match item["type"].as_str() {
+        Some(kind @ ("comment" | "pollopt")) => Err(ApiError::Invalid(format!(
+            "item {} is a {}, not a story",
+            id, kind
+        ))),
+        _ => Ok(Some(serde_json::from_value(item)?)),
+    }
+}
+
+// A user's profile, or `None` if there is no such user.
+// HN user names are letters, digits, `-` and `_`; anything else would end
+// up in the API path.
+pub fn is_valid_username(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+pub async fn fetch_user(name: &str) -> Result<Option<User>, ApiError> {
+    if !is_valid_username(name) {
+        return Err(ApiError::Invalid(format!(
+            "not an HN user name: {:?}",
+            name
+        )));
+    }
+    let url = format!("https://hacker-news.firebaseio.com/v0/user/{}.json", name);
+    Ok(http::client().get_json(&url).await?)
+}
+
+pub async fn fetch_comments(story: &Story) -> Result<Vec<FlatComment>, ApiError> {
+    fetch_comments_to_depth(story, COMMENT_DEPTH).await
+}
+
+// The thread flattened depth-first, following replies `max_depth` levels
+// below the top-level comments.
+pub async fn fetch_comments_to_depth(
     story: &Story,
-) -> Result<Vec<FlatComment>, Box<dyn Error + Send + Sync>> {
-    let client = reqwest::Client::new();
+    max_depth: usize,
+) -> Result<Vec<FlatComment>, ApiError> {
+    let _task = diagnostics::start_task(format!("comments for {}", story.id));
     let mut flat_comments = Vec::new();
-    fetch_comment_tree(&client, &story.kids, 0, &mut flat_comments, 4).await?;
+    fetch_comment_tree(
+        http::client(),
+        &story.kids,
+        0,
+        &mut flat_comments,
+        max_depth,
+    )
+    .await?;
     Ok(flat_comments)
 }
 
+// A poll's options in the order HN lists them; empty for other stories.
+// Options that are deleted or fail to load are left out rather than keeping
+// the thread from opening.
+pub async fn fetch_poll_options(story: &Story) -> Vec<PollOption> {
+    let mut options = Vec::new();
+    for &id in &story.parts {
+        let item: serde_json::Value = match http::client().get_json(&item_api_url(id)).await {
+            Ok(item) => item,
+            Err(e) => {
+                tracing::warn!(id, error = %e, "skipping poll option");
+                continue;
+            }
+        };
+        if item.is_null() || item["deleted"] == true || item["dead"] == true {
+            continue;
+        }
+        match serde_json::from_value(item) {
+            Ok(option) => options.push(option),
+            Err(e) => {
+                tracing::warn!(id, error = %e, "skipping unreadable poll option");
+                diagnostics::record_error("decode");
+            }
+        }
+    }
+    options
+}
+
 async fn fetch_comment_tree(
-    client: &reqwest::Client,
+    client: &HttpClient,
     kid_ids: &[u32],
     depth: usize,
     out: &mut Vec<FlatComment>,
     max_depth: usize,
-) -> Result<(), Box<dyn Error + Send + Sync>> {
+) -> Result<(), ApiError> {
     for &id in kid_ids {
-        let url = format!("https://hacker-news.firebaseio.com/v0/item/{}.json", id);
-        let resp = client.get(&url).send().await?;
-        let comment: Comment = match resp.json().await {
+        let comment: Comment = match client.get_json(&item_api_url(id)).await {
             Ok(c) => c,
-            Err(_) => continue,
+            Err(e) if e.is_decode() => {
+                tracing::warn!(id, error = %e, "skipping unreadable comment");
+                diagnostics::record_error("decode");
+                continue;
+            }
+            Err(e) => return Err(e.into()),
         };
         if comment.deleted || comment.dead {
             continue;
@@ -71,9 +308,29 @@ async fn fetch_comment_tree(
     Ok(())
 }
 
-pub async fn get_claude_summary(text: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
-    let client = reqwest::Client::new();
+// What a story's summary is written from: its title, link and text.
+pub fn summary_prompt(story: &Story) -> String {
+    let mut text = story.title.clone();
+    if let Some(url) = &story.url {
+        text.push_str(&format!("\n{}", url));
+    }
+    if let Some(body) = &story.text {
+        text.push_str(&format!("\n\n{}", strip_html(body).trim()));
+    }
+    text
+}
+
+pub async fn get_claude_summary(text: &str) -> Result<String, ApiError> {
+    let api_key = std::env::var("CLAUDE_API_KEY")
+        .ok()
+        .filter(|key| !key.is_empty())
+        .ok_or_else(|| {
+            ApiError::Auth("CLAUDE_API_KEY is not set; export it to use summaries".to_string())
+        })?;
+    request_summary(CLAUDE_API_URL, &api_key, text).await
+}
 
+async fn request_summary(api_url: &str, api_key: &str, text: &str) -> Result<String, ApiError> {
     let request = ClaudeRequest {
         model: "claude-3-opus-20240229".to_string(),
         messages: vec![Message {
@@ -86,12 +343,116 @@ pub async fn get_claude_summary(text: &str) -> Result<String, Box<dyn Error + Se
         max_tokens: 150,
     };
 
-    let response = client
-        .post("https://api.anthropic.com/v1/messages")
-        .header("x-api-key", std::env::var("CLAUDE_API_KEY")?)
-        .json(&request)
-        .send()
+    let _task = diagnostics::start_task("Claude summary");
+    let started = Instant::now();
+    let response = http::client()
+        .send(|client| {
+            client
+                .post(api_url)
+                .timeout(SUMMARY_TIMEOUT)
+                .header("x-api-key", api_key)
+                .header("anthropic-version", ANTHROPIC_VERSION)
+                .json(&request)
+        })
         .await?;
 
-    Ok(response.text().await?)
+    // Claude explains refusals in the body, e.g. an unknown model or an
+    // overloaded service.
+    let status = response.status();
+    tracing::info!(
+        model = %request.model,
+        prompt_chars = text.len(),
+        status = status.as_u16(),
+        latency_ms = started.elapsed().as_millis() as u64,
+        "Claude summary"
+    );
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|v| v["error"]["message"].as_str().map(str::to_string))
+            .unwrap_or_else(|| status.to_string());
+        tracing::warn!(status = status.as_u16(), %message, "Claude refused the summary");
+        return Err(match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                ApiError::Auth(format!("Claude rejected CLAUDE_API_KEY: {}", message))
+            }
+            _ => ApiError::Llm { status, message },
+        });
+    }
+
+    // The summary is the text of the first content block.
+    let body: serde_json::Value = response.json().await?;
+    body["content"][0]["text"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| ApiError::Decode("Claude's reply has no summary text".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mock_server::{MockServer, Request, Response};
+
+    #[test]
+    fn accepts_only_hn_user_names() {
+        for name in ["pg", "dang", "tptacek", "John_Doe-2"] {
+            assert!(is_valid_username(name), "{}", name);
+        }
+        for name in ["", "../item/1", "a b", "a?print=pretty", "ünïcode", "a.b"] {
+            assert!(!is_valid_username(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn prompts_with_the_title_link_and_text() {
+        let story: Story = serde_json::from_str(
+            r#"{"id":1,"title":"Ask HN: Tabs?","url":"https://example.com/","text":"<p>Tabs &amp; spaces</p>","by":"pg","score":3}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            summary_prompt(&story),
+            "Ask HN: Tabs?\nhttps://example.com/\n\nTabs & spaces"
+        );
+    }
+
+    #[tokio::test]
+    async fn sends_the_headers_the_messages_api_requires() {
+        let server = MockServer::start(|_: &Request| {
+            Response::json(r#"{"content":[{"type":"text","text":"A short summary."}]}"#)
+        })
+        .await;
+        let url = format!("{}/v1/messages", server.url);
+        let summary = request_summary(&url, "sk-test", "Show HN: a thing")
+            .await
+            .unwrap();
+        assert_eq!(summary, "A short summary.");
+
+        let request = &server.requests()[0];
+        assert_eq!(request.method(), "POST");
+        assert_eq!(request.target(), "/v1/messages");
+        assert_eq!(request.header("x-api-key"), Some("sk-test"));
+        assert_eq!(request.header("anthropic-version"), Some("2023-06-01"));
+        assert_eq!(request.header("content-type"), Some("application/json"));
+        let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body["max_tokens"], 150);
+        assert!(body["messages"][0]["content"]
+            .as_str()
+            .unwrap()
+            .ends_with("Show HN: a thing"));
+    }
+
+    #[tokio::test]
+    async fn reports_claude_refusals_with_their_message() {
+        let server = MockServer::start(|_: &Request| {
+            Response::new(401, r#"{"error":{"message":"invalid x-api-key"}}"#)
+        })
+        .await;
+        let error = request_summary(&server.url, "sk-bad", "text")
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ApiError::Auth(_)));
+        assert!(error.to_string().contains("invalid x-api-key"), "{}", error);
+    }
 }
@@ -0,0 +1,380 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use regex::Regex;
+use reqwest::header::{COOKIE, LOCATION, SET_COOKIE};
+use serde::{Deserialize, Serialize};
+
+use crate::diagnostics;
+use crate::error::ApiError;
+use crate::http::{self, HttpClient};
+
+pub const DEFAULT_BASE_URL: &str = "https://news.ycombinator.com";
+
+// A logged-in HN session: the `user` cookie HN sets on login, sent back with
+// every page and action.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub username: String,
+    pub cookie: String,
+}
+
+// Something the account can do to a story or comment. Hiding only applies to
+// stories.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ItemAction {
+    Upvote,
+    Unvote,
+    Favorite,
+    Unfavorite,
+    Hide,
+    Unhide,
+}
+
+impl ItemAction {
+    // HN endpoint and the query parameters that pick the action there.
+    fn endpoint(&self) -> (&'static str, &'static str) {
+        match self {
+            ItemAction::Upvote => ("vote", "how=up&"),
+            ItemAction::Unvote => ("vote", "how=un&"),
+            ItemAction::Favorite => ("fave", ""),
+            ItemAction::Unfavorite => ("fave", "un=t&"),
+            ItemAction::Hide => ("hide", ""),
+            ItemAction::Unhide => ("hide", "un=t&"),
+        }
+    }
+
+    // Status message once HN has accepted it.
+    pub fn done(&self) -> &'static str {
+        match self {
+            ItemAction::Upvote => "Upvoted",
+            ItemAction::Unvote => "Vote removed",
+            ItemAction::Favorite => "Added to favorites",
+            ItemAction::Unfavorite => "Removed from favorites",
+            ItemAction::Hide => "Hidden on HN",
+            ItemAction::Unhide => "No longer hidden on HN",
+        }
+    }
+}
+
+// The action links on an HN page, each with the auth token HN requires for
+// that item and action. A story's page has the vote links of all its
+// comments; a comment's favorite link is only on the comment's own page.
+#[derive(Debug, Default)]
+pub struct ItemLinks {
+    tokens: HashMap<(u32, ItemAction), String>,
+}
+
+impl ItemLinks {
+    // Picks the links out of a page's HTML, e.g.
+    // `href='vote?id=8863&amp;how=up&amp;auth=1a2b...&amp;goto=item%3Fid%3D8863'`.
+    pub fn parse(page: &str) -> ItemLinks {
+        static LINK: OnceLock<Regex> = OnceLock::new();
+        let link = LINK.get_or_init(|| {
+            Regex::new(r#"href=['"](vote|fave|hide)\?([^'"]+)['"]"#).expect("valid regex")
+        });
+
+        let mut tokens = HashMap::new();
+        for caps in link.captures_iter(page) {
+            let query = caps[2].replace("&amp;", "&");
+            let param = |name: &str| {
+                query
+                    .split('&')
+                    .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+            };
+            let (Some(id), Some(auth)) =
+                (param("id").and_then(|id| id.parse().ok()), param("auth"))
+            else {
+                continue;
+            };
+            let undo = param("un").is_some();
+            let action = match (&caps[1], param("how")) {
+                ("vote", Some("up")) => ItemAction::Upvote,
+                ("vote", Some("un")) => ItemAction::Unvote,
+                ("fave", _) if undo => ItemAction::Unfavorite,
+                ("fave", _) => ItemAction::Favorite,
+                ("hide", _) if undo => ItemAction::Unhide,
+                ("hide", _) => ItemAction::Hide,
+                _ => continue,
+            };
+            tokens.insert((id, action), auth.to_string());
+        }
+        ItemLinks { tokens }
+    }
+
+    pub fn token(&self, id: u32, action: ItemAction) -> Option<&str> {
+        self.tokens.get(&(id, action)).map(String::as_str)
+    }
+
+    // Whether the page has anything for `id`.
+    pub fn mentions(&self, id: u32) -> bool {
+        self.tokens.keys().any(|&(item, _)| item == id)
+    }
+
+    // Items with a vote link, and whether the account has upvoted each. HN
+    // keeps the (invisible) up arrow after a vote, so the unvote link is what
+    // tells.
+    pub fn votes(&self) -> impl Iterator<Item = (u32, bool)> + '_ {
+        self.tokens
+            .keys()
+            .filter(|(_, action)| *action == ItemAction::Upvote)
+            .map(|&(id, _)| (id, self.tokens.contains_key(&(id, ItemAction::Unvote))))
+    }
+
+    // Items with a favorite link, and whether each is a favorite already.
+    pub fn favorites(&self) -> impl Iterator<Item = (u32, bool)> + '_ {
+        self.tokens.keys().filter_map(|&(id, action)| match action {
+            ItemAction::Favorite => Some((id, false)),
+            ItemAction::Unfavorite => Some((id, true)),
+            _ => None,
+        })
+    }
+}
+
+fn session_expired() -> ApiError {
+    ApiError::Auth("the HN session has expired; log in again".to_string())
+}
+
+// Client for the parts of news.ycombinator.com the API doesn't cover: logging
+// in and acting on items as the logged-in user.
+#[derive(Clone)]
+pub struct HnWeb {
+    http: HttpClient,
+    base_url: String,
+}
+
+impl Default for HnWeb {
+    fn default() -> Self {
+        HnWeb::new(DEFAULT_BASE_URL)
+    }
+}
+
+impl HnWeb {
+    pub fn new(base_url: &str) -> HnWeb {
+        HnWeb {
+            http: http::web_client().clone(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    // Logs in with HN's own form. A good login is answered with a redirect
+    // that sets the `user` cookie; a bad one with the login page again.
+    pub async fn login(&self, username: &str, password: &str) -> Result<Session, ApiError> {
+        let _task = diagnostics::start_task("HN login");
+        let url = format!("{}/login", self.base_url);
+        let form = [("acct", username), ("pw", password), ("goto", "news")];
+        let response = self
+            .http
+            .send(|client| client.post(&url).form(&form))
+            .await?
+            .error_for_status()?;
+        let cookie = response
+            .headers()
+            .get_all(SET_COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok()?.split(';').next())
+            .find(|cookie| cookie.starts_with("user="))
+            .map(str::to_string);
+        if let Some(cookie) = cookie {
+            tracing::info!(username, "logged in to HN");
+            return Ok(Session {
+                username: username.to_string(),
+                cookie,
+            });
+        }
+        let page = response.text().await?;
+        Err(ApiError::Auth(if page.contains("Validation required") {
+            "HN wants a captcha for this login; log in once in the browser, then try again"
+                .to_string()
+        } else {
+            "HN login failed; check the username and password".to_string()
+        }))
+    }
+
+    // The action links on item `id`'s page, as the session's user sees it.
+    pub async fn item_links(&self, session: &Session, id: u32) -> Result<ItemLinks, ApiError> {
+        let _task = diagnostics::start_task(format!("HN page {}", id));
+        let url = format!("{}/item?id={}", self.base_url, id);
+        let page = self
+            .http
+            .send_idempotent(|client| client.get(&url).header(COOKIE, &session.cookie))
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        // Logged-in pages link to the logout page; anything else means HN
+        // no longer accepts the cookie.
+        if !page.contains("logout?") {
+            return Err(session_expired());
+        }
+        Ok(ItemLinks::parse(&page))
+    }
+
+    // Performs `action` on item `id` with the token from its link. HN
+    // redirects back on success; a page in reply means it refused.
+    pub async fn act(
+        &self,
+        session: &Session,
+        id: u32,
+        action: ItemAction,
+        auth: &str,
+    ) -> Result<(), ApiError> {
+        let _task = diagnostics::start_task(format!("HN {:?} {}", action, id));
+        let (endpoint, params) = action.endpoint();
+        let url = format!(
+            "{}/{}?id={}&{}auth={}&goto=news",
+            self.base_url, endpoint, id, params, auth
+        );
+        let response = self
+            .http
+            .send(|client| client.get(&url).header(COOKIE, &session.cookie))
+            .await?
+            .error_for_status()?;
+        if response.status().is_redirection() {
+            let to_login = response
+                .headers()
+                .get(LOCATION)
+                .and_then(|location| location.to_str().ok())
+                .is_some_and(|location| location.contains("login"));
+            if to_login {
+                return Err(session_expired());
+            }
+            tracing::info!(id, ?action, "HN action done");
+            return Ok(());
+        }
+        let page = response.text().await?;
+        if page.contains("name=\"acct\"") || page.contains("name='acct'") {
+            return Err(session_expired());
+        }
+        let reason = crate::ui::strip_html(&page);
+        let reason = reason.split_whitespace().collect::<Vec<_>>().join(" ");
+        Err(ApiError::Invalid(if reason.is_empty() {
+            "HN didn't accept that".to_string()
+        } else {
+            format!("HN: {}", reason.chars().take(80).collect::<String>())
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mock_server::{MockServer, Request, Response};
+
+    // A minimal stand-in for news.ycombinator.com: `alice`/`secret` logs in,
+    // item 1 is a story with one comment, 2, and votes succeed for the
+    // session's tokens.
+    async fn fake_hn() -> MockServer {
+        MockServer::start(|request: &Request| {
+            let logged_in = request.header("Cookie") == Some("user=alice&t0k");
+            let path = request.target();
+            if path == "/login" {
+                if request.body == "acct=alice&pw=secret&goto=news" {
+                    Response::redirect("news")
+                        .header("Set-Cookie", "user=alice&t0k; Secure; HttpOnly")
+                } else {
+                    Response::ok("Bad login.<form><input name=\"acct\"></form>")
+                }
+            } else if path == "/item?id=1" && logged_in {
+                Response::ok(STORY_PAGE)
+            } else if path.starts_with("/item") {
+                Response::ok("<a href=\"login?goto=news\">login</a>")
+            } else if logged_in && path.contains("auth=aaa") {
+                Response::redirect("news")
+            } else if logged_in {
+                Response::ok("Can't make that vote.")
+            } else {
+                Response::redirect("login?goto=news")
+            }
+        })
+        .await
+    }
+
+    // Story 1 not yet voted on; comment 2 upvoted and a favorite.
+    const STORY_PAGE: &str = r#"
+        <a id="logout" href="logout?auth=zzz&amp;goto=item%3Fid%3D1">logout</a>
+        <a id='up_1' class='clicky' href='vote?id=1&amp;how=up&amp;auth=aaa&amp;goto=item%3Fid%3D1'></a>
+        <a href="hide?id=1&amp;auth=bbb&amp;goto=item%3Fid%3D1">hide</a>
+        <a href="fave?id=1&amp;auth=ccc">favorite</a>
+        <a id='up_2' class='clicky nosee' href='vote?id=2&amp;how=up&amp;auth=ddd&amp;goto=item%3Fid%3D1#2'></a>
+        <a id='un_2' class='clicky' href='vote?id=2&amp;how=un&amp;auth=eee&amp;goto=item%3Fid%3D1#2'>unvote</a>
+        <a href="fave?id=2&amp;un=t&amp;auth=fff">un-favorite</a>
+    "#;
+
+    fn alice() -> Session {
+        Session {
+            username: "alice".to_string(),
+            cookie: "user=alice&t0k".to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_action_links_and_vote_state() {
+        let links = ItemLinks::parse(STORY_PAGE);
+        assert_eq!(links.token(1, ItemAction::Upvote), Some("aaa"));
+        assert_eq!(links.token(1, ItemAction::Hide), Some("bbb"));
+        assert_eq!(links.token(1, ItemAction::Favorite), Some("ccc"));
+        assert_eq!(links.token(2, ItemAction::Unvote), Some("eee"));
+        assert_eq!(links.token(2, ItemAction::Unfavorite), Some("fff"));
+        assert_eq!(links.token(2, ItemAction::Hide), None);
+
+        let mut votes: Vec<_> = links.votes().collect();
+        votes.sort();
+        assert_eq!(votes, vec![(1, false), (2, true)]);
+        let mut favorites: Vec<_> = links.favorites().collect();
+        favorites.sort();
+        assert_eq!(favorites, vec![(1, false), (2, true)]);
+    }
+
+    #[tokio::test]
+    async fn logs_in_and_keeps_the_user_cookie() {
+        let server = fake_hn().await;
+        let web = HnWeb::new(&server.url);
+        let session = web.login("alice", "secret").await.unwrap();
+        assert_eq!(session.username, "alice");
+        assert_eq!(session.cookie, "user=alice&t0k");
+
+        let error = web.login("alice", "wrong").await.unwrap_err();
+        assert!(matches!(error, ApiError::Auth(_)), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn votes_with_the_token_from_the_item_page() {
+        let server = fake_hn().await;
+        let web = HnWeb::new(&server.url);
+        let links = web.item_links(&alice(), 1).await.unwrap();
+        let auth = links.token(1, ItemAction::Upvote).unwrap();
+        web.act(&alice(), 1, ItemAction::Upvote, auth)
+            .await
+            .unwrap();
+
+        let vote = server.requests().pop().unwrap();
+        assert_eq!(vote.method(), "GET");
+        assert_eq!(vote.target(), "/vote?id=1&how=up&auth=aaa&goto=news");
+        assert_eq!(vote.header("Cookie"), Some("user=alice&t0k"));
+    }
+
+    #[tokio::test]
+    async fn reports_refused_actions_and_expired_sessions() {
+        let server = fake_hn().await;
+        let web = HnWeb::new(&server.url);
+        let refused = web
+            .act(&alice(), 1, ItemAction::Upvote, "stale")
+            .await
+            .unwrap_err();
+        assert_eq!(refused.to_string(), "HN: Can't make that vote.");
+
+        let stranger = Session {
+            username: "alice".to_string(),
+            cookie: "user=alice&old".to_string(),
+        };
+        let expired = web.item_links(&stranger, 1).await.unwrap_err();
+        assert!(matches!(expired, ApiError::Auth(_)));
+        let expired = web
+            .act(&stranger, 1, ItemAction::Upvote, "aaa")
+            .await
+            .unwrap_err();
+        assert!(matches!(expired, ApiError::Auth(_)));
+    }
+}
@@ -0,0 +1,866 @@
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::config::Config;
+use crate::types::Mode;
+
+// Everything a key can do. Actions are looked up per mode, so the same action
+// (e.g. `MoveDown`) moves through stories, comments or menu entries depending
+// on where it is triggered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    Back,
+    Select,
+    MoveDown,
+    MoveUp,
+    MoveTop,
+    MoveBottom,
+    OpenStory,
+    OpenComments,
+    OpenCommentsInBrowser,
+    OpenMenu,
+    Search,
+    CommitFilter,
+    RemoteSearch,
+    ToggleSearchKind,
+    ToggleSearchSort,
+    CommandPalette,
+    Refresh,
+    RefreshAll,
+    NextSection,
+    PreviousSection,
+    SectionTop,
+    SectionAsk,
+    SectionShow,
+    SectionJobs,
+    SectionSaved,
+    Bookmark,
+    MuteUser,
+    MuteDomain,
+    MutePattern,
+    ToggleHidden,
+    CycleSort,
+    NextNewComment,
+    PreviousNewComment,
+    ToggleCollapse,
+    NextMatch,
+    PreviousMatch,
+    Reply,
+    Export,
+    GoTo,
+    Summarize,
+    Upvote,
+    Favorite,
+    HideStory,
+    LogIn,
+    LogOut,
+    ToggleDebug,
+    Suspend,
+    DeleteChar,
+}
+
+impl Action {
+    pub const ALL: &'static [Action] = &[
+        Action::Quit,
+        Action::Back,
+        Action::Select,
+        Action::MoveDown,
+        Action::MoveUp,
+        Action::MoveTop,
+        Action::MoveBottom,
+        Action::OpenStory,
+        Action::OpenComments,
+        Action::OpenCommentsInBrowser,
+        Action::OpenMenu,
+        Action::Search,
+        Action::CommitFilter,
+        Action::RemoteSearch,
+        Action::ToggleSearchKind,
+        Action::ToggleSearchSort,
+        Action::CommandPalette,
+        Action::Refresh,
+        Action::RefreshAll,
+        Action::NextSection,
+        Action::PreviousSection,
+        Action::SectionTop,
+        Action::SectionAsk,
+        Action::SectionShow,
+        Action::SectionJobs,
+        Action::SectionSaved,
+        Action::Bookmark,
+        Action::MuteUser,
+        Action::MuteDomain,
+        Action::MutePattern,
+        Action::ToggleHidden,
+        Action::CycleSort,
+        Action::NextNewComment,
+        Action::PreviousNewComment,
+        Action::ToggleCollapse,
+        Action::NextMatch,
+        Action::PreviousMatch,
+        Action::Reply,
+        Action::Export,
+        Action::GoTo,
+        Action::Summarize,
+        Action::Upvote,
+        Action::Favorite,
+        Action::HideStory,
+        Action::LogIn,
+        Action::LogOut,
+        Action::ToggleDebug,
+        Action::Suspend,
+        Action::DeleteChar,
+    ];
+
+    // Name used to refer to this action in the config file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Back => "back",
+            Action::Select => "select",
+            Action::MoveDown => "move-down",
+            Action::MoveUp => "move-up",
+            Action::MoveTop => "move-top",
+            Action::MoveBottom => "move-bottom",
+            Action::OpenStory => "open-story",
+            Action::OpenComments => "open-comments",
+            Action::OpenCommentsInBrowser => "open-comments-in-browser",
+            Action::OpenMenu => "open-menu",
+            Action::Search => "search",
+            Action::CommitFilter => "commit-filter",
+            Action::RemoteSearch => "remote-search",
+            Action::ToggleSearchKind => "toggle-search-kind",
+            Action::ToggleSearchSort => "toggle-search-sort",
+            Action::CommandPalette => "command-palette",
+            Action::Refresh => "refresh",
+            Action::RefreshAll => "refresh-all",
+            Action::NextSection => "next-section",
+            Action::PreviousSection => "previous-section",
+            Action::SectionTop => "section-top",
+            Action::SectionAsk => "section-ask",
+            Action::SectionShow => "section-show",
+            Action::SectionJobs => "section-jobs",
+            Action::SectionSaved => "section-saved",
+            Action::Bookmark => "bookmark",
+            Action::MuteUser => "mute-user",
+            Action::MuteDomain => "mute-domain",
+            Action::MutePattern => "mute-pattern",
+            Action::ToggleHidden => "toggle-hidden",
+            Action::CycleSort => "cycle-sort",
+            Action::NextNewComment => "next-new-comment",
+            Action::PreviousNewComment => "previous-new-comment",
+            Action::ToggleCollapse => "toggle-collapse",
+            Action::NextMatch => "next-match",
+            Action::PreviousMatch => "previous-match",
+            Action::Reply => "reply",
+            Action::Export => "export",
+            Action::GoTo => "go-to",
+            Action::Summarize => "summarize",
+            Action::Upvote => "upvote",
+            Action::Favorite => "favorite",
+            Action::HideStory => "hide-story",
+            Action::LogIn => "log-in",
+            Action::LogOut => "log-out",
+            Action::ToggleDebug => "toggle-debug",
+            Action::Suspend => "suspend",
+            Action::DeleteChar => "delete-char",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.iter().copied().find(|a| a.name() == name)
+    }
+}
+
+// A single key press, normalized so that `Shift` is folded into the character
+// itself (`G` rather than `shift+g`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Chord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Chord {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Chord {
+        let modifiers = match code {
+            KeyCode::Char(_) | KeyCode::BackTab => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Chord { code, modifiers }
+    }
+
+    pub fn from_event(key: KeyEvent) -> Chord {
+        Chord::new(key.code, key.modifiers)
+    }
+
+    // Parses a single chord such as `j`, `G`, `ctrl+k`, `alt+enter` or `pagedown`.
+    fn parse(s: &str) -> Result<Chord, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        let prefixes = [
+            ("ctrl+", KeyModifiers::CONTROL),
+            ("alt+", KeyModifiers::ALT),
+            ("shift+", KeyModifiers::SHIFT),
+        ];
+        while let Some((prefix, flag)) = prefixes.iter().find(|(p, _)| {
+            rest.len() > p.len()
+                && rest
+                    .get(..p.len())
+                    .is_some_and(|h| h.eq_ignore_ascii_case(p))
+        }) {
+            modifiers |= *flag;
+            rest = &rest[prefix.len()..];
+        }
+
+        let code = match parse_key_name(rest) {
+            // Terminals send Shift+Tab as its own key.
+            Some(KeyCode::Tab) if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+            Some(code) => code,
+            None => {
+                let mut chars = rest.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => {
+                        if modifiers.contains(KeyModifiers::SHIFT) {
+                            KeyCode::Char(c.to_ascii_uppercase())
+                        } else if modifiers.contains(KeyModifiers::CONTROL) {
+                            KeyCode::Char(c.to_ascii_lowercase())
+                        } else {
+                            KeyCode::Char(c)
+                        }
+                    }
+                    _ => return Err(format!("unknown key '{}'", s)),
+                }
+            }
+        };
+        Ok(Chord::new(code, modifiers))
+    }
+
+    pub fn label(&self) -> String {
+        let key = match self.code {
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Enter => "Enter".to_string(),
+            KeyCode::Esc => "Esc".to_string(),
+            KeyCode::Tab => "Tab".to_string(),
+            KeyCode::BackTab => "Shift+Tab".to_string(),
+            KeyCode::Backspace => "Bksp".to_string(),
+            KeyCode::Delete => "Del".to_string(),
+            KeyCode::Insert => "Ins".to_string(),
+            KeyCode::Up => "↑".to_string(),
+            KeyCode::Down => "↓".to_string(),
+            KeyCode::Left => "←".to_string(),
+            KeyCode::Right => "→".to_string(),
+            KeyCode::Home => "Home".to_string(),
+            KeyCode::End => "End".to_string(),
+            KeyCode::PageUp => "PgUp".to_string(),
+            KeyCode::PageDown => "PgDn".to_string(),
+            KeyCode::F(n) => format!("F{}", n),
+            _ => "?".to_string(),
+        };
+        let mut label = String::new();
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            label.push_str("Ctrl+");
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            label.push_str("Alt+");
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            label.push_str("Shift+");
+        }
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            label.push_str(&key.to_uppercase());
+        } else {
+            label.push_str(&key);
+        }
+        label
+    }
+}
+
+fn parse_key_name(name: &str) -> Option<KeyCode> {
+    let code = match name.to_lowercase().as_str() {
+        "enter" | "return" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "backspace" | "bksp" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" | "ins" => KeyCode::Insert,
+        "space" => KeyCode::Char(' '),
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" | "pgup" => KeyCode::PageUp,
+        "pagedown" | "pgdn" => KeyCode::PageDown,
+        other => {
+            let n = other.strip_prefix('f')?.parse::<u8>().ok()?;
+            if (1..=12).contains(&n) {
+                KeyCode::F(n)
+            } else {
+                return None;
+            }
+        }
+    };
+    Some(code)
+}
+
+// Parses a key sequence. Chords are separated by whitespace (`ctrl+x ctrl+c`),
+// and a run of plain characters is a sequence of single keys (`gg`).
+pub fn parse_sequence(s: &str) -> Result<Vec<Chord>, String> {
+    let mut chords = Vec::new();
+    for token in s.split_whitespace() {
+        if token.contains('+') || parse_key_name(token).is_some() {
+            chords.push(Chord::parse(token)?);
+        } else {
+            for c in token.chars() {
+                chords.push(Chord::new(KeyCode::Char(c), KeyModifiers::NONE));
+            }
+        }
+    }
+    if chords.is_empty() {
+        return Err("empty key sequence".to_string());
+    }
+    Ok(chords)
+}
+
+pub enum KeyResolution {
+    Action(Action),
+    // The key is a prefix of a longer binding; wait for the next key.
+    Pending,
+    Unbound(KeyEvent),
+}
+
+pub struct Keymap {
+    bindings: HashMap<Mode, Vec<(Vec<Chord>, Action)>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Keymap {
+    pub fn new() -> Keymap {
+        let mut keymap = Keymap {
+            bindings: HashMap::new(),
+        };
+        for &(mode, defaults) in DEFAULT_BINDINGS {
+            for &(keys, action) in defaults {
+                let sequence = parse_sequence(keys).expect("invalid default key binding");
+                keymap.bind(mode, sequence, action);
+            }
+        }
+        keymap
+    }
+
+    // Builds the default keymap and applies the overrides from `[keys.<mode>]`.
+    // Invalid entries are skipped and reported back so the UI can show them.
+    pub fn from_config(config: &Config) -> (Keymap, Vec<String>) {
+        let mut keymap = Keymap::new();
+        let mut errors = Vec::new();
+        for (mode_name, overrides) in &config.keys {
+            let Some(mode) = Mode::ALL.iter().copied().find(|m| m.as_str() == mode_name) else {
+                errors.push(format!("unknown mode '{}' in [keys]", mode_name));
+                continue;
+            };
+            for (keys, action_name) in overrides {
+                let sequence = match parse_sequence(keys) {
+                    Ok(sequence) => sequence,
+                    Err(e) => {
+                        errors.push(format!("[keys.{}] {}", mode_name, e));
+                        continue;
+                    }
+                };
+                if action_name == "none" {
+                    keymap.unbind(mode, &sequence);
+                } else if let Some(action) = Action::from_name(action_name) {
+                    keymap.bind(mode, sequence, action);
+                } else {
+                    errors.push(format!(
+                        "[keys.{}] unknown action '{}'",
+                        mode_name, action_name
+                    ));
+                }
+            }
+        }
+        (keymap, errors)
+    }
+
+    pub fn bind(&mut self, mode: Mode, sequence: Vec<Chord>, action: Action) {
+        let bindings = self.bindings.entry(mode).or_default();
+        bindings.retain(|(existing, _)| *existing != sequence);
+        bindings.push((sequence, action));
+    }
+
+    pub fn unbind(&mut self, mode: Mode, sequence: &[Chord]) {
+        if let Some(bindings) = self.bindings.get_mut(&mode) {
+            bindings.retain(|(existing, _)| existing != sequence);
+        }
+    }
+
+    // Feeds one key press into the pending sequence. An exact match wins over a
+    // longer binding sharing the same prefix.
+    pub fn resolve(&self, mode: Mode, pending: &mut Vec<Chord>, key: KeyEvent) -> KeyResolution {
+        let chord = Chord::from_event(key);
+        pending.push(chord);
+        let bindings = self.bindings.get(&mode).map(Vec::as_slice).unwrap_or(&[]);
+
+        if let Some((_, action)) = bindings.iter().find(|(seq, _)| seq == pending) {
+            pending.clear();
+            return KeyResolution::Action(*action);
+        }
+        if bindings
+            .iter()
+            .any(|(seq, _)| seq.len() > pending.len() && seq.starts_with(pending))
+        {
+            return KeyResolution::Pending;
+        }
+
+        // Not part of any sequence: retry the key on its own so that a stray
+        // prefix key doesn't swallow the next key press.
+        let had_prefix = pending.len() > 1;
+        pending.clear();
+        if had_prefix {
+            return self.resolve(mode, pending, key);
+        }
+        KeyResolution::Unbound(key)
+    }
+
+    pub fn keys_for(&self, mode: Mode, action: Action) -> Vec<&[Chord]> {
+        self.bindings
+            .get(&mode)
+            .map(|bindings| {
+                bindings
+                    .iter()
+                    .filter(|(_, a)| *a == action)
+                    .map(|(seq, _)| seq.as_slice())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    // Label of the first key bound to `action`, e.g. "gg" or "Ctrl+K".
+    pub fn key_label(&self, mode: Mode, action: Action) -> Option<String> {
+        self.keys_for(mode, action)
+            .first()
+            .map(|seq| seq.iter().map(Chord::label).collect())
+    }
+
+    // Shortcuts shown in the help bar for `mode`, built from the active bindings
+    // so remapped keys are always reflected.
+    pub fn help_entries(&self, mode: Mode) -> Vec<(String, &'static str)> {
+        help_layout(mode)
+            .iter()
+            .filter_map(|&(actions, desc)| {
+                let labels: Vec<String> = actions
+                    .iter()
+                    .filter_map(|&a| self.key_label(mode, a))
+                    .collect();
+                if labels.is_empty() {
+                    None
+                } else {
+                    Some((labels.join("/"), desc))
+                }
+            })
+            .collect()
+    }
+}
+
+fn help_layout(mode: Mode) -> &'static [(&'static [Action], &'static str)] {
+    match mode {
+        Mode::Normal => &[
+            (&[Action::MoveDown, Action::MoveUp], "navigate"),
+            (&[Action::PreviousSection, Action::NextSection], "sections"),
+            (&[Action::OpenStory], "open"),
+            (&[Action::OpenComments], "comments"),
+            (&[Action::OpenMenu], "options"),
+            (&[Action::Bookmark], "bookmark"),
+            (&[Action::CycleSort], "sort"),
+            (&[Action::Search], "search"),
+            (&[Action::CommandPalette], "palette"),
+            (&[Action::Refresh], "refresh"),
+            (&[Action::Quit], "quit"),
+        ],
+        Mode::Comments => &[
+            (&[Action::MoveDown, Action::MoveUp], "navigate"),
+            (&[Action::OpenStory], "open"),
+            (&[Action::PreviousNewComment, Action::NextNewComment], "new"),
+            (&[Action::ToggleCollapse], "collapse"),
+            (&[Action::Search], "search"),
+            (&[Action::NextMatch, Action::PreviousMatch], "matches"),
+            (&[Action::Reply], "reply"),
+            (&[Action::Bookmark], "bookmark"),
+            (&[Action::Refresh], "refresh"),
+            (&[Action::CommandPalette], "palette"),
+            (&[Action::Back], "back"),
+        ],
+        Mode::Search => &[
+            (&[Action::MoveDown, Action::MoveUp], "navigate"),
+            (&[Action::CommitFilter], "keep filter"),
+            (&[Action::Back], "cancel"),
+        ],
+        Mode::Menu | Mode::CommandPalette => &[
+            (&[Action::MoveDown, Action::MoveUp], "navigate"),
+            (&[Action::Select], "select"),
+            (&[Action::Back], "close"),
+        ],
+        Mode::Summary => &[(&[Action::Back], "close")],
+        Mode::RemoteSearch => &[
+            (&[Action::MoveDown, Action::MoveUp], "navigate"),
+            (&[Action::Select], "search/open"),
+            (&[Action::ToggleSearchKind], "stories/comments"),
+            (&[Action::ToggleSearchSort], "sort"),
+            (&[Action::Back], "close"),
+        ],
+        Mode::Prompt => &[(&[Action::Select], "confirm"), (&[Action::Back], "cancel")],
+        Mode::CommentSearch => &[
+            (&[Action::NextMatch, Action::PreviousMatch], "matches"),
+            (&[Action::Select], "done"),
+            (&[Action::Back], "clear"),
+        ],
+    }
+}
+
+type ModeBindings = &'static [(&'static str, Action)];
+
+const DEFAULT_BINDINGS: &[(Mode, ModeBindings)] = &[
+    (
+        Mode::Normal,
+        &[
+            ("q", Action::Quit),
+            ("ctrl+c", Action::Quit),
+            ("ctrl+z", Action::Suspend),
+            ("ctrl+k", Action::CommandPalette),
+            ("f12", Action::ToggleDebug),
+            ("esc", Action::Back),
+            ("/", Action::Search),
+            ("F", Action::RemoteSearch),
+            ("gi", Action::GoTo),
+            ("j", Action::MoveDown),
+            ("down", Action::MoveDown),
+            ("k", Action::MoveUp),
+            ("up", Action::MoveUp),
+            ("gg", Action::MoveTop),
+            ("G", Action::MoveBottom),
+            ("r", Action::Refresh),
+            ("R", Action::RefreshAll),
+            ("T", Action::SectionTop),
+            ("A", Action::SectionAsk),
+            ("S", Action::SectionShow),
+            ("J", Action::SectionJobs),
+            ("B", Action::SectionSaved),
+            ("b", Action::Bookmark),
+            ("u", Action::Upvote),
+            ("f", Action::Favorite),
+            ("x", Action::HideStory),
+            ("M", Action::MuteUser),
+            ("D", Action::MuteDomain),
+            ("X", Action::MutePattern),
+            ("H", Action::ToggleHidden),
+            ("s", Action::CycleSort),
+            ("h", Action::PreviousSection),
+            ("l", Action::NextSection),
+            ("enter", Action::OpenStory),
+            ("o", Action::OpenMenu),
+            ("c", Action::OpenComments),
+            ("C", Action::OpenCommentsInBrowser),
+        ],
+    ),
+    (
+        Mode::Menu,
+        &[
+            ("esc", Action::Back),
+            ("enter", Action::Select),
+            ("j", Action::MoveDown),
+            ("down", Action::MoveDown),
+            ("k", Action::MoveUp),
+            ("up", Action::MoveUp),
+            ("ctrl+k", Action::CommandPalette),
+            ("f12", Action::ToggleDebug),
+            ("ctrl+z", Action::Suspend),
+        ],
+    ),
+    (
+        Mode::Summary,
+        &[
+            ("esc", Action::Back),
+            ("ctrl+k", Action::CommandPalette),
+            ("f12", Action::ToggleDebug),
+            ("ctrl+z", Action::Suspend),
+        ],
+    ),
+    (
+        Mode::Comments,
+        &[
+            ("esc", Action::Back),
+            ("q", Action::Back),
+            ("j", Action::MoveDown),
+            ("down", Action::MoveDown),
+            ("k", Action::MoveUp),
+            ("up", Action::MoveUp),
+            ("gg", Action::MoveTop),
+            ("G", Action::MoveBottom),
+            ("]", Action::NextNewComment),
+            ("[", Action::PreviousNewComment),
+            ("space", Action::ToggleCollapse),
+            ("/", Action::Search),
+            ("n", Action::NextMatch),
+            ("N", Action::PreviousMatch),
+            ("o", Action::OpenStory),
+            ("enter", Action::OpenStory),
+            ("r", Action::Reply),
+            ("e", Action::Export),
+            ("gi", Action::GoTo),
+            ("R", Action::Refresh),
+            ("b", Action::Bookmark),
+            ("u", Action::Upvote),
+            ("f", Action::Favorite),
+            ("M", Action::MuteUser),
+            ("H", Action::ToggleHidden),
+            ("ctrl+k", Action::CommandPalette),
+            ("f12", Action::ToggleDebug),
+            ("ctrl+z", Action::Suspend),
+        ],
+    ),
+    (
+        Mode::CommandPalette,
+        &[
+            ("esc", Action::Back),
+            ("enter", Action::Select),
+            ("down", Action::MoveDown),
+            ("up", Action::MoveUp),
+            ("backspace", Action::DeleteChar),
+            ("ctrl+z", Action::Suspend),
+        ],
+    ),
+    (
+        Mode::Search,
+        &[
+            ("esc", Action::Back),
+            ("enter", Action::CommitFilter),
+            ("down", Action::MoveDown),
+            ("up", Action::MoveUp),
+            ("backspace", Action::DeleteChar),
+            ("ctrl+k", Action::CommandPalette),
+            ("ctrl+z", Action::Suspend),
+        ],
+    ),
+    (
+        Mode::RemoteSearch,
+        &[
+            ("esc", Action::Back),
+            ("enter", Action::Select),
+            ("down", Action::MoveDown),
+            ("up", Action::MoveUp),
+            ("backspace", Action::DeleteChar),
+            ("tab", Action::ToggleSearchKind),
+            ("ctrl+s", Action::ToggleSearchSort),
+            ("ctrl+k", Action::CommandPalette),
+            ("f12", Action::ToggleDebug),
+            ("ctrl+z", Action::Suspend),
+        ],
+    ),
+    (
+        Mode::Prompt,
+        &[
+            ("esc", Action::Back),
+            ("enter", Action::Select),
+            ("backspace", Action::DeleteChar),
+            ("ctrl+z", Action::Suspend),
+        ],
+    ),
+    (
+        Mode::CommentSearch,
+        &[
+            ("esc", Action::Back),
+            ("enter", Action::Select),
+            ("down", Action::NextMatch),
+            ("up", Action::PreviousMatch),
+            ("backspace", Action::DeleteChar),
+            ("ctrl+z", Action::Suspend),
+        ],
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    fn char_key(c: char) -> KeyEvent {
+        let modifiers = if c.is_ascii_uppercase() {
+            KeyModifiers::SHIFT
+        } else {
+            KeyModifiers::NONE
+        };
+        key(KeyCode::Char(c), modifiers)
+    }
+
+    // Feeds `keys` to the keymap in `mode`, returning the last resolution.
+    fn press(keymap: &Keymap, mode: Mode, pending: &mut Vec<Chord>, keys: &[KeyEvent]) -> String {
+        let mut last = String::new();
+        for &k in keys {
+            last = match keymap.resolve(mode, pending, k) {
+                KeyResolution::Action(action) => action.name().to_string(),
+                KeyResolution::Pending => "pending".to_string(),
+                KeyResolution::Unbound(_) => "unbound".to_string(),
+            };
+        }
+        last
+    }
+
+    fn config_with(mode: &str, keys: &[(&str, &str)]) -> Config {
+        let mut config = Config::default();
+        config.keys.insert(
+            mode.to_string(),
+            keys.iter()
+                .map(|(k, a)| (k.to_string(), a.to_string()))
+                .collect(),
+        );
+        config
+    }
+
+    #[test]
+    fn folds_shift_into_uppercase_letters() {
+        let shifted = Chord::parse("shift+g").unwrap();
+        assert_eq!(shifted, Chord::parse("G").unwrap());
+        assert_eq!(shifted, Chord::from_event(char_key('G')));
+        assert_eq!(shifted.label(), "G");
+        assert_eq!(
+            Chord::parse("ctrl+K").unwrap(),
+            Chord::new(KeyCode::Char('k'), KeyModifiers::CONTROL)
+        );
+    }
+
+    #[test]
+    fn shift_tab_is_back_tab() {
+        let chord = Chord::parse("shift+tab").unwrap();
+        assert_eq!(
+            chord,
+            Chord::from_event(key(KeyCode::BackTab, KeyModifiers::SHIFT))
+        );
+        assert_eq!(chord, Chord::parse("backtab").unwrap());
+        assert_eq!(chord.label(), "Shift+Tab");
+        assert_ne!(chord, Chord::parse("tab").unwrap());
+    }
+
+    #[test]
+    fn tells_letters_from_function_keys() {
+        assert_eq!(
+            parse_sequence("F").unwrap(),
+            vec![Chord::new(KeyCode::Char('F'), KeyModifiers::NONE)]
+        );
+        assert_eq!(
+            parse_sequence("f1").unwrap(),
+            vec![Chord::new(KeyCode::F(1), KeyModifiers::NONE)]
+        );
+        assert_eq!(
+            parse_sequence("F12").unwrap(),
+            vec![Chord::new(KeyCode::F(12), KeyModifiers::NONE)]
+        );
+        assert!(parse_sequence("ctrl+nope").is_err());
+        assert!(parse_sequence("  ").is_err());
+    }
+
+    #[test]
+    fn parses_runs_and_chord_sequences() {
+        let g = Chord::new(KeyCode::Char('g'), KeyModifiers::NONE);
+        assert_eq!(parse_sequence("gg").unwrap(), vec![g, g]);
+        assert_eq!(parse_sequence("g g").unwrap(), vec![g, g]);
+        let ctrl = |c| Chord::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        assert_eq!(
+            parse_sequence("ctrl+x ctrl+c").unwrap(),
+            vec![ctrl('x'), ctrl('c')]
+        );
+    }
+
+    #[test]
+    fn waits_for_the_rest_of_a_sequence() {
+        let keymap = Keymap::new();
+        let mut pending = Vec::new();
+        assert_eq!(
+            press(&keymap, Mode::Normal, &mut pending, &[char_key('g')]),
+            "pending"
+        );
+        assert_eq!(
+            press(&keymap, Mode::Normal, &mut pending, &[char_key('g')]),
+            "move-top"
+        );
+        assert!(pending.is_empty());
+        assert_eq!(
+            press(
+                &keymap,
+                Mode::Normal,
+                &mut pending,
+                &[char_key('g'), char_key('i')]
+            ),
+            "go-to"
+        );
+        // A stray prefix doesn't swallow the next key.
+        assert_eq!(
+            press(
+                &keymap,
+                Mode::Normal,
+                &mut pending,
+                &[char_key('g'), char_key('j')]
+            ),
+            "move-down"
+        );
+        assert!(pending.is_empty());
+        assert_eq!(
+            press(&keymap, Mode::Normal, &mut pending, &[char_key('G')]),
+            "move-bottom"
+        );
+        assert_eq!(
+            press(&keymap, Mode::Normal, &mut pending, &[char_key('z')]),
+            "unbound"
+        );
+    }
+
+    #[test]
+    fn config_overrides_replace_conflicting_bindings() {
+        let config = config_with(
+            "normal",
+            &[
+                ("j", "move-up"),
+                ("g", "refresh"),
+                ("q", "none"),
+                ("x", "nope"),
+            ],
+        );
+        let (keymap, errors) = Keymap::from_config(&config);
+        assert_eq!(
+            errors,
+            vec!["[keys.normal] unknown action 'nope'".to_string()]
+        );
+
+        let mut pending = Vec::new();
+        let mut run = |k| press(&keymap, Mode::Normal, &mut pending, &[k]);
+        assert_eq!(run(char_key('j')), "move-up");
+        // An exact match wins over the longer `gg` and `gi`.
+        assert_eq!(run(char_key('g')), "refresh");
+        assert_eq!(run(char_key('q')), "unbound");
+        // Other bindings for the same action stay.
+        assert_eq!(run(key(KeyCode::Down, KeyModifiers::NONE)), "move-down");
+        assert_eq!(
+            keymap.key_label(Mode::Normal, Action::MoveUp).as_deref(),
+            Some("k")
+        );
+    }
+
+    #[test]
+    fn reports_unknown_modes_and_keys() {
+        let (_, errors) = Keymap::from_config(&config_with("nowhere", &[("j", "quit")]));
+        assert_eq!(errors, vec!["unknown mode 'nowhere' in [keys]".to_string()]);
+        let (_, errors) = Keymap::from_config(&config_with("normal", &[("hyper+j", "quit")]));
+        assert_eq!(errors.len(), 1);
+    }
+}
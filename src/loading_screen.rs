@@ -1,13 +1,15 @@
 use rand::Rng;
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
+    style::Style,
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph},
     Frame,
 };
 use std::time::Instant;
 
+use crate::theme::Theme;
+
 pub struct MatrixRain {
     chars: Vec<Vec<char>>,
     speeds: Vec<f32>,
@@ -15,10 +17,13 @@ pub struct MatrixRain {
     last_update: Instant,
     blink_state: bool,
     blink_timer: Instant,
+    // Without animation only a steady "Loading..." box is shown.
+    animated: bool,
 }
 
 impl MatrixRain {
-    pub fn new(width: usize) -> Self {
+    pub fn new(width: usize, animated: bool) -> Self {
+        let width = if animated { width } else { 0 };
         let mut rng = rand::rng();
         let matrix_chars = "ｱｲｳｴｵｶｷｸｹｺｻｼｽｾｿﾀﾁﾂﾃﾄﾅﾆﾇﾈﾉﾊﾋﾌﾍﾎﾏﾐﾑﾒﾓﾔﾕﾖﾗﾘﾙﾚﾛﾜﾝ1234567890"
             .chars()
@@ -37,10 +42,14 @@ impl MatrixRain {
             last_update: Instant::now(),
             blink_state: true,
             blink_timer: Instant::now(),
+            animated,
         }
     }
 
     pub fn update(&mut self) {
+        if !self.animated {
+            return;
+        }
         let elapsed = self.last_update.elapsed().as_secs_f32();
         self.last_update = Instant::now();
 
@@ -57,7 +66,7 @@ impl MatrixRain {
         }
     }
 
-    pub fn draw(&self, f: &mut Frame, area: Rect) {
+    pub fn draw(&self, f: &mut Frame, area: Rect, theme: &Theme) {
         let mut lines = Vec::new();
         for y in 0..area.height.saturating_sub(2) as usize {
             let mut line = Vec::new();
@@ -69,7 +78,7 @@ impl MatrixRain {
                 if intensity <= 0.0 {
                     line.push(Span::styled(
                         self.chars[x][char_index].to_string(),
-                        Style::default().fg(Color::Green),
+                        theme.rain,
                     ));
                 } else {
                     line.push(Span::styled(" ", Style::default()));
@@ -88,12 +97,12 @@ impl MatrixRain {
         };
 
         let loading_block = Paragraph::new(loading_text)
-            .style(Style::default().fg(Color::Green))
+            .style(theme.text)
             .alignment(Alignment::Center)
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::Green)),
+                    .border_style(theme.text),
             );
 
         let loading_area = centered_rect(10, 8, area);
@@ -1,13 +1,28 @@
-use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
-use ratatui::{backend::CrosstermBackend, Terminal};
+use crossterm::event::{self, Event, KeyEventKind};
 use std::error::Error;
-use std::io;
+use std::process::ExitCode;
+use std::time::Duration;
+mod account;
+mod algolia;
+mod bookmarks;
+mod cli;
+mod config;
+mod diagnostics;
+mod error;
+mod export;
+mod filters;
+mod history;
 mod hn_api;
+mod hn_web;
+mod http;
+mod keymap;
 mod loading_screen;
+#[cfg(test)]
+mod mock_server;
+mod palette;
+mod query;
+mod terminal;
+mod theme;
 mod types;
 mod ui;
 
@@ -16,187 +31,42 @@ pub mod app {
 }
 
 mod app_impl {
-    use std::error::Error;
-    use std::io;
+    use std::io::Write;
     use std::time::Duration;
 
-    use crossterm::event::{self, Event, KeyCode};
-    use ratatui::{backend::CrosstermBackend, Terminal};
+    use crossterm::event::{
+        self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent,
+        MouseEventKind,
+    };
+    use ratatui::layout::{Position, Rect};
 
+    use crate::account::Account;
+    use crate::algolia::{RemoteSearch, SearchKind, SearchQuery, SearchSort};
+    use crate::bookmarks::{Bookmark, Bookmarks};
+    use crate::diagnostics;
+    use crate::error::{ApiError, AppError};
+    use crate::export::{ExportFormat, ExportTarget};
+    use crate::filters::Filters;
+    use crate::history::History;
     use crate::hn_api::fetch_comments;
+    use crate::hn_api::fetch_poll_options;
     use crate::hn_api::fetch_stories;
+    use crate::hn_api::{get_claude_summary, summary_prompt};
+    use crate::hn_api::{resolve_item, HnLink, ItemTarget};
+    use crate::hn_web::{HnWeb, ItemAction, ItemLinks};
+    use crate::keymap::{Action, Chord, KeyResolution, Keymap};
     use crate::loading_screen::MatrixRain;
-    use crate::types::{FlatComment, Mode, Section, Story};
+    use crate::palette::CommandPalette;
+    use crate::query::StoryQuery;
+    use crate::terminal::{self, Signals, Tui};
+    use crate::theme::Theme;
+    use crate::types::{
+        Comment, FlatComment, Mode, PollOption, Prompt, PromptKind, Section, SortOrder, Story,
+    };
+    use crate::ui::{strip_html, HitMap};
 
-    pub struct Command {
-        pub name: String,
-        pub description: String,
-        pub action: fn(&mut App) -> Result<(), Box<dyn Error + Send + Sync>>,
-    }
-
-    pub struct CommandPalette {
-        pub commands: Vec<Command>,
-        pub filtered_commands: Vec<usize>,
-        pub search_query: String,
-        pub selected_index: usize,
-    }
-
-    impl Default for CommandPalette {
-        fn default() -> Self {
-            Self::new()
-        }
-    }
-
-    impl CommandPalette {
-        pub fn new() -> Self {
-            CommandPalette {
-                commands: vec![
-                    Command {
-                        name: "Open in Browser".to_string(),
-                        description: "Open the selected story in your default browser".to_string(),
-                        action: |app| {
-                            app.open_current_story();
-                            Ok(())
-                        },
-                    },
-                    Command {
-                        name: "Open Comments".to_string(),
-                        description: "Open the comments for the selected story".to_string(),
-                        action: |app| {
-                            app.open_comments();
-                            Ok(())
-                        },
-                    },
-                    Command {
-                        name: "Summarize".to_string(),
-                        description: "Get an AI summary of the selected story".to_string(),
-                        action: |app| {
-                            app.show_menu = true;
-                            app.mode = Mode::Menu;
-                            app.menu_index = 0;
-                            Ok(())
-                        },
-                    },
-                    Command {
-                        name: "Search".to_string(),
-                        description: "Filter stories by text".to_string(),
-                        action: |app| {
-                            app.mode = Mode::Search;
-                            app.search_query.clear();
-                            app.filtered_stories = (0..app.stories.len()).collect();
-                            Ok(())
-                        },
-                    },
-                    Command {
-                        name: "Switch to Top".to_string(),
-                        description: "Switch to Top stories section".to_string(),
-                        action: |app| {
-                            app.current_section = Section::Top;
-                            app.set_status_message("Switching to Top stories...".to_string());
-                            Ok(())
-                        },
-                    },
-                    Command {
-                        name: "Switch to Ask".to_string(),
-                        description: "Switch to Ask HN section".to_string(),
-                        action: |app| {
-                            app.current_section = Section::Ask;
-                            app.set_status_message("Switching to Ask HN...".to_string());
-                            Ok(())
-                        },
-                    },
-                    Command {
-                        name: "Switch to Show".to_string(),
-                        description: "Switch to Show HN section".to_string(),
-                        action: |app| {
-                            app.current_section = Section::Show;
-                            app.set_status_message("Switching to Show HN...".to_string());
-                            Ok(())
-                        },
-                    },
-                    Command {
-                        name: "Switch to Jobs".to_string(),
-                        description: "Switch to Jobs section".to_string(),
-                        action: |app| {
-                            app.current_section = Section::Jobs;
-                            app.set_status_message("Switching to Jobs...".to_string());
-                            Ok(())
-                        },
-                    },
-                    Command {
-                        name: "Refresh".to_string(),
-                        description: "Refresh the current section".to_string(),
-                        action: |app| {
-                            app.set_status_message("Refreshing...".to_string());
-                            Ok(())
-                        },
-                    },
-                    Command {
-                        name: "Refresh All".to_string(),
-                        description: "Refresh all sections".to_string(),
-                        action: |app| {
-                            app.set_status_message("Refreshing all sections...".to_string());
-                            Ok(())
-                        },
-                    },
-                    Command {
-                        name: "Quit".to_string(),
-                        description: "Exit the application".to_string(),
-                        action: |_app| {
-                            std::process::exit(0);
-                        },
-                    },
-                ],
-                filtered_commands: Vec::new(),
-                search_query: String::new(),
-                selected_index: 0,
-            }
-        }
-
-        pub fn filter_commands(&mut self) {
-            if self.search_query.is_empty() {
-                self.filtered_commands = (0..self.commands.len()).collect();
-            } else {
-                self.filtered_commands = self
-                    .commands
-                    .iter()
-                    .enumerate()
-                    .filter(|(_, cmd)| {
-                        cmd.name
-                            .to_lowercase()
-                            .contains(&self.search_query.to_lowercase())
-                            || cmd
-                                .description
-                                .to_lowercase()
-                                .contains(&self.search_query.to_lowercase())
-                    })
-                    .map(|(i, _)| i)
-                    .collect();
-            }
-            self.selected_index = 0;
-        }
-
-        pub fn next_command(&mut self) {
-            if !self.filtered_commands.is_empty() {
-                self.selected_index = (self.selected_index + 1) % self.filtered_commands.len();
-            }
-        }
-
-        pub fn previous_command(&mut self) {
-            if !self.filtered_commands.is_empty() {
-                self.selected_index = self
-                    .selected_index
-                    .checked_sub(1)
-                    .unwrap_or(self.filtered_commands.len() - 1);
-            }
-        }
-
-        pub fn get_selected_command(&self) -> Option<&Command> {
-            self.filtered_commands
-                .get(self.selected_index)
-                .map(|&idx| &self.commands[idx])
-        }
-    }
+    // Two clicks on the same spot within this window count as a double-click.
+    const DOUBLE_CLICK: Duration = Duration::from_millis(400);
 
     pub struct App {
         pub stories: Vec<Story>,
@@ -212,12 +82,61 @@ mod app_impl {
         pub cached_stories: std::collections::HashMap<Section, Vec<Story>>,
         pub command_palette: CommandPalette,
         pub search_query: String,
+        // Why `search_query` doesn't parse, if it doesn't.
+        pub search_error: Option<String>,
         pub filtered_stories: Vec<usize>,
         pub comments: Vec<FlatComment>,
         pub comments_selected: usize,
         pub comments_scroll: usize,
-        pub comments_story_title: String,
-        pub comments_story_id: u32,
+        // Story whose thread is shown in the comments view.
+        pub comments_story: Option<Story>,
+        // Comments that weren't in the thread on the previous visit.
+        pub new_comments: std::collections::HashSet<u32>,
+        // Comments whose replies are folded away.
+        pub collapsed: std::collections::HashSet<u32>,
+        // In-thread search: the query and the indices of matching comments.
+        pub comment_query: String,
+        pub comment_matches: Vec<usize>,
+        // Mode to go back to when leaving the comments view.
+        pub comments_return_mode: Mode,
+        // Comment to select once the next thread finishes loading.
+        pub pending_comment_focus: Option<u32>,
+        // Options of the poll shown in the comments view, if it is one.
+        pub poll_options: Vec<PollOption>,
+        // Comments leading down to `pending_comment_focus`, spliced into the
+        // thread if they're deeper than what gets loaded.
+        pub pending_thread: Vec<Comment>,
+        pub keymap: Keymap,
+        pub pending_keys: Vec<Chord>,
+        pub theme: Theme,
+        pub signals: Signals,
+        pub hit_map: HitMap,
+        pub last_click: Option<(std::time::Instant, u16, u16)>,
+        pub bookmarks: Bookmarks,
+        pub history: History,
+        pub filters: Filters,
+        // Show muted stories and comments instead of hiding them.
+        pub show_hidden: bool,
+        // Stories in the current section matched by a mute rule.
+        pub hidden_count: usize,
+        pub sort: SortOrder,
+        pub remote_search: RemoteSearch,
+        // Bookmark being filled in through the tag and note prompts.
+        pub pending_bookmark: Option<Bookmark>,
+        pub prompt: Option<Prompt>,
+        // Exports sent to `-`, printed once the terminal is restored.
+        pub stdout_exports: Vec<String>,
+        // Matrix rain while loading; off with `--no-animation`.
+        pub animation: bool,
+        // Request log and task list drawn over whatever is on screen.
+        pub show_debug: bool,
+        pub account: Account,
+        pub hn_web: HnWeb,
+        // Username typed into the login prompt, while the password is asked.
+        pub pending_login: Option<String>,
+        // Action links from HN pages, by page id, so acting again on the
+        // same thread doesn't fetch it again.
+        pub item_links: std::collections::HashMap<u32, ItemLinks>,
     }
 
     impl Default for App {
@@ -242,33 +161,201 @@ mod app_impl {
                 cached_stories: std::collections::HashMap::new(),
                 command_palette: CommandPalette::new(),
                 search_query: String::new(),
+                search_error: None,
                 filtered_stories: Vec::new(),
                 comments: Vec::new(),
                 comments_selected: 0,
                 comments_scroll: 0,
-                comments_story_title: String::new(),
-                comments_story_id: 0,
+                comments_story: None,
+                new_comments: std::collections::HashSet::new(),
+                collapsed: std::collections::HashSet::new(),
+                comment_query: String::new(),
+                comment_matches: Vec::new(),
+                comments_return_mode: Mode::Normal,
+                pending_comment_focus: None,
+                pending_thread: Vec::new(),
+                poll_options: Vec::new(),
+                keymap: Keymap::new(),
+                pending_keys: Vec::new(),
+                theme: Theme::default(),
+                signals: Signals::default(),
+                hit_map: HitMap::default(),
+                last_click: None,
+                bookmarks: Bookmarks::default(),
+                history: History::default(),
+                filters: Filters::default(),
+                show_hidden: false,
+                hidden_count: 0,
+                sort: SortOrder::Rank,
+                remote_search: RemoteSearch::default(),
+                pending_bookmark: None,
+                prompt: None,
+                stdout_exports: Vec::new(),
+                animation: true,
+                show_debug: false,
+                account: Account::default(),
+                hn_web: HnWeb::default(),
+                pending_login: None,
+                item_links: std::collections::HashMap::new(),
             }
         }
 
+        // Whether `story` is kept out of the list: muted, or hidden on HN.
+        pub fn hides_story(&self, story: &Story) -> bool {
+            self.filters.hides_story(story) || self.account.is_hidden(story.id)
+        }
+
+        // Muted stories are dropped unless revealed, and the rest is put in
+        // the current sort order. Bookmarks are never hidden or re-sorted:
+        // saving them was deliberate, and their order is the save order.
         pub fn set_stories(&mut self, stories: Vec<Story>) {
-            self.stories = stories;
+            self.hidden_count = 0;
+            self.stories = if self.current_section == Section::Saved {
+                stories
+            } else {
+                let total = stories.len();
+                let mut shown: Vec<Story> = stories
+                    .into_iter()
+                    .filter(|s| self.show_hidden || !self.hides_story(s))
+                    .collect();
+                self.hidden_count = if self.show_hidden {
+                    shown.iter().filter(|s| self.hides_story(s)).count()
+                } else {
+                    total - shown.len()
+                };
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_or(0, |d| d.as_secs());
+                self.sort.sort(&mut shown, now);
+                shown
+            };
             self.filtered_stories = (0..self.stories.len()).collect();
             self.selected_index = 0;
+            if !self.search_query.is_empty() {
+                self.filter_stories();
+            }
+        }
+
+        // The selected story, if it's in the (possibly filtered) list.
+        // `selected_index` always indexes `stories`; `filtered_stories` is the
+        // visible order.
+        pub fn selected_story(&self) -> Option<&Story> {
+            self.filtered_stories
+                .contains(&self.selected_index)
+                .then(|| self.stories.get(self.selected_index))
+                .flatten()
+        }
+
+        // Row of the selected story in the visible list.
+        pub fn selected_row(&self) -> usize {
+            self.filtered_stories
+                .iter()
+                .position(|&i| i == self.selected_index)
+                .unwrap_or(0)
+        }
+
+        fn select_row(&mut self, row: usize) {
+            if let Some(&i) = self.filtered_stories.get(row) {
+                self.selected_index = i;
+            }
+        }
+
+        // Re-filters the current section after the mute rules or the reveal
+        // toggle changed, keeping the selection where possible.
+        fn reapply_filters(&mut self) {
+            let selected = self.selected_story().map(|s| s.id);
+            let index = self.selected_index;
+            if let Some(stories) = self.section_stories(self.current_section) {
+                self.set_stories(stories);
+            }
+            self.selected_index = selected
+                .and_then(|id| self.stories.iter().position(|s| s.id == id))
+                .unwrap_or_else(|| index.min(self.stories.len().saturating_sub(1)));
+            if !self.search_query.is_empty() {
+                self.filter_stories();
+            }
+        }
+
+        fn cycle_sort(&mut self) {
+            if self.current_section == Section::Saved {
+                self.set_status_message(
+                    "Saved stories stay in the order they were saved".to_string(),
+                );
+                return;
+            }
+            self.sort = self.sort.next();
+            self.reapply_filters();
+            self.set_status_message(format!("Sorted by {}", self.sort.as_str()));
+        }
+
+        fn toggle_show_hidden(&mut self) {
+            self.show_hidden = !self.show_hidden;
+            self.reapply_filters();
+            self.set_status_message(if self.show_hidden {
+                "Showing muted items".to_string()
+            } else {
+                "Hiding muted items".to_string()
+            });
+        }
+
+        // Mutes the author of the selected story or comment.
+        fn mute_selected_user(&mut self) {
+            let user = match self.mode {
+                Mode::Normal => self.selected_story().map(|s| s.by.clone()),
+                Mode::Comments => self
+                    .comments
+                    .get(self.comments_selected)
+                    .map(|fc| fc.comment.by.clone()),
+                _ => None,
+            };
+            let Some(user) = user.filter(|u| !u.is_empty()) else {
+                return;
+            };
+            match self.filters.toggle_user(&user) {
+                Ok(true) => self.set_status_message(format!("Muted {}", user)),
+                Ok(false) => self.set_status_message(format!("Unmuted {}", user)),
+                Err(e) => self.set_status_message(e),
+            }
+            self.reapply_filters();
+        }
+
+        fn mute_selected_domain(&mut self) {
+            let domain = self.selected_story().and_then(|s| s.domain());
+            let Some(domain) = domain else {
+                self.set_status_message("Story has no link to mute".to_string());
+                return;
+            };
+            match self.filters.toggle_domain(&domain) {
+                Ok(true) => self.set_status_message(format!("Muted {}", domain)),
+                Ok(false) => self.set_status_message(format!("Unmuted {}", domain)),
+                Err(e) => self.set_status_message(e),
+            }
+            self.reapply_filters();
         }
 
         pub fn next_story(&mut self) {
-            if !self.stories.is_empty() {
-                self.selected_index = (self.selected_index + 1) % self.stories.len();
+            if !self.filtered_stories.is_empty() {
+                self.select_row((self.selected_row() + 1) % self.filtered_stories.len());
             }
         }
 
         pub fn previous_story(&mut self) {
-            if !self.stories.is_empty() {
-                self.selected_index = self
-                    .selected_index
+            if !self.filtered_stories.is_empty() {
+                let row = self
+                    .selected_row()
                     .checked_sub(1)
-                    .unwrap_or(self.stories.len() - 1);
+                    .unwrap_or(self.filtered_stories.len() - 1);
+                self.select_row(row);
+            }
+        }
+
+        // The mode whose screen is drawn underneath the command palette or an
+        // input prompt.
+        pub fn base_mode(&self) -> Mode {
+            match self.mode {
+                Mode::CommandPalette => self.command_palette.context,
+                Mode::Prompt => self.prompt.as_ref().map_or(Mode::Normal, |p| p.return_mode),
+                mode => mode,
             }
         }
 
@@ -276,18 +363,39 @@ mod app_impl {
             self.status_message = Some((message, std::time::Instant::now()));
         }
 
+        // Shows a failure in the status bar. Ones that may clear up on their
+        // own say which key tries `retry` again.
+        pub fn report_error(&mut self, context: &str, error: impl Into<AppError>, retry: Action) {
+            let error = error.into();
+            tracing::warn!(context, error = %error, "reported to the user");
+            let mut message = format!("{}: {}", context, error);
+            if error.is_retryable() {
+                match self.keymap.key_label(self.base_mode(), retry) {
+                    Some(key) => message.push_str(&format!(" (press {} to retry)", key)),
+                    None => message.push_str(" (try again)"),
+                }
+            }
+            self.set_status_message(message);
+        }
+
         pub fn open_current_story(&mut self) {
-            if let Some(story) = self.stories.get(self.selected_index) {
+            if let Some(story) = self.selected_story().cloned() {
                 if let Some(url) = &story.url {
                     match open::that(url) {
-                        Ok(_) => self.set_status_message("Opened in browser".to_string()),
+                        Ok(_) => {
+                            self.set_status_message("Opened in browser".to_string());
+                            let result = self.history.mark_article_opened(story.id);
+                            self.report_history_error(result);
+                        }
                         Err(_) => self.set_status_message("Failed to open URL".to_string()),
                     }
                 } else {
                     let hn_url = format!("https://news.ycombinator.com/item?id={}", story.id);
                     match open::that(&hn_url) {
                         Ok(_) => {
-                            self.set_status_message("Opened discussion in browser".to_string())
+                            self.set_status_message("Opened discussion in browser".to_string());
+                            let result = self.history.mark_thread_opened(&story);
+                            self.report_history_error(result);
                         }
                         Err(_) => self.set_status_message("Failed to open discussion".to_string()),
                     }
@@ -295,86 +403,467 @@ mod app_impl {
             }
         }
 
+        // Failing to record a visit shouldn't get in the way, but say so.
+        fn report_history_error(&mut self, result: Result<(), String>) {
+            if let Err(e) = result {
+                self.set_status_message(e);
+            }
+        }
+
+        // Stories for `section`: the bookmarked ones for "Saved", otherwise
+        // whatever was last fetched for the feed.
+        fn section_stories(&self, section: Section) -> Option<Vec<Story>> {
+            match section {
+                Section::Saved => Some(self.bookmarks.stories()),
+                _ => self.cached_stories.get(&section).cloned(),
+            }
+        }
+
+        // Rebuilds the list after bookmarks change while "Saved" is shown.
+        fn refresh_saved(&mut self) {
+            if self.current_section == Section::Saved {
+                let selected = self.selected_index;
+                self.set_stories(self.bookmarks.stories());
+                self.selected_index = selected.min(self.stories.len().saturating_sub(1));
+                if !self.search_query.is_empty() {
+                    self.filter_stories();
+                }
+            }
+        }
+
+        // Removes `story`/`comment` from the bookmarks if saved, otherwise
+        // asks for tags and a note and saves it.
+        fn toggle_bookmark(&mut self, story: Story, comment: Option<Comment>) {
+            let comment_id = comment.as_ref().map(|c| c.id);
+            if self.bookmarks.position(story.id, comment_id).is_some() {
+                match self.bookmarks.remove(story.id, comment_id) {
+                    Ok(_) => self.set_status_message("Bookmark removed".to_string()),
+                    Err(e) => self.set_status_message(e),
+                }
+                self.refresh_saved();
+                return;
+            }
+            self.pending_bookmark = Some(Bookmark::new(story, comment));
+            self.start_prompt(PromptKind::BookmarkTags, "Tags (comma separated)");
+        }
+
+        fn bookmark_selected(&mut self) {
+            match self.mode {
+                Mode::Normal => {
+                    if let Some(story) = self.selected_story().cloned() {
+                        // A row in "Saved" may stand for a comment bookmark.
+                        let comment = match self.current_section {
+                            Section::Saved => self
+                                .bookmarks
+                                .items
+                                .get(self.selected_index)
+                                .and_then(|b| b.comment.clone()),
+                            _ => None,
+                        };
+                        self.toggle_bookmark(story, comment);
+                    }
+                }
+                Mode::Comments => {
+                    let comment = self
+                        .comments
+                        .get(self.comments_selected)
+                        .map(|fc| fc.comment.clone());
+                    if let (Some(story), Some(comment)) = (self.comments_story.clone(), comment) {
+                        self.toggle_bookmark(story, Some(comment));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        fn start_prompt(&mut self, kind: PromptKind, label: &str) {
+            let return_mode = self.mode;
+            self.prompt = Some(Prompt {
+                kind,
+                label: label.to_string(),
+                input: String::new(),
+                return_mode,
+            });
+            self.mode = Mode::Prompt;
+        }
+
+        fn cancel_prompt(&mut self) {
+            if let Some(prompt) = self.prompt.take() {
+                self.mode = prompt.return_mode;
+            }
+            self.pending_bookmark = None;
+            self.pending_login = None;
+        }
+
+        fn submit_prompt(&mut self) {
+            let Some(prompt) = self.prompt.take() else {
+                return;
+            };
+            self.mode = prompt.return_mode;
+            match prompt.kind {
+                PromptKind::BookmarkTags => {
+                    if let Some(bookmark) = self.pending_bookmark.as_mut() {
+                        bookmark.set_tags(&prompt.input);
+                        self.start_prompt(PromptKind::BookmarkNote, "Note");
+                    }
+                }
+                PromptKind::MutePattern => {
+                    let pattern = prompt.input.trim();
+                    if pattern.is_empty() {
+                        return;
+                    }
+                    match self.filters.add_keyword(pattern) {
+                        Ok(()) => self
+                            .set_status_message(format!("Hiding stories matching /{}/", pattern)),
+                        Err(e) => self.set_status_message(e),
+                    }
+                    self.reapply_filters();
+                }
+                PromptKind::ExportPath => self.export_thread(&prompt.input),
+                PromptKind::LoginUser => {
+                    let username = prompt.input.trim();
+                    if !username.is_empty() {
+                        self.pending_login = Some(username.to_string());
+                        self.start_prompt(PromptKind::LoginPassword, "HN password");
+                    }
+                }
+                // Need the network, so `handle_action` takes care of them.
+                PromptKind::GoTo | PromptKind::LoginPassword => {}
+                PromptKind::BookmarkNote => {
+                    if let Some(mut bookmark) = self.pending_bookmark.take() {
+                        bookmark.note = prompt.input.trim().to_string();
+                        let what = if bookmark.comment.is_some() {
+                            "Comment"
+                        } else {
+                            "Story"
+                        };
+                        match self.bookmarks.add(bookmark) {
+                            Ok(()) => self.set_status_message(format!("{} bookmarked", what)),
+                            Err(e) => self.set_status_message(e),
+                        }
+                        self.refresh_saved();
+                    }
+                }
+            }
+        }
+
+        fn start_log_in(&mut self) {
+            let username = self.account.session().map(|s| s.username.clone());
+            self.start_prompt(PromptKind::LoginUser, "HN username");
+            if let (Some(prompt), Some(username)) = (self.prompt.as_mut(), username) {
+                prompt.input = username;
+            }
+        }
+
+        // Logs in with the username from the first prompt and the password
+        // from the second.
+        async fn log_in(&mut self, password: &str, terminal: &mut Tui) {
+            let Some(username) = self.pending_login.take() else {
+                return;
+            };
+            self.set_status_message("Logging in to HN...".to_string());
+            self.redraw(terminal);
+            match self.hn_web.login(&username, password).await {
+                Ok(session) => {
+                    self.item_links.clear();
+                    match self.account.log_in(session) {
+                        Ok(()) => self.set_status_message(format!("Logged in as {}", username)),
+                        Err(e) => self.set_status_message(e),
+                    }
+                    self.reapply_filters();
+                }
+                Err(e) => self.report_error("Login failed", e, Action::LogIn),
+            }
+        }
+
+        // Only the local copy of the cookie goes; HN keeps the session until
+        // it expires or is logged out in the browser.
+        fn log_out(&mut self) {
+            let Some(username) = self.account.session().map(|s| s.username.clone()) else {
+                return self.set_status_message("Not logged in to HN".to_string());
+            };
+            self.item_links.clear();
+            match self.account.log_out() {
+                Ok(()) => self.set_status_message(format!(
+                    "Logged out {} here; the HN session itself stays valid",
+                    username
+                )),
+                Err(e) => self.set_status_message(e),
+            }
+            self.reapply_filters();
+        }
+
+        // The item account actions apply to, and the HN pages with its action
+        // links: the story's page, and for a comment also its own page, the
+        // only one with its favorite link.
+        fn account_target(&self) -> Option<(u32, Vec<u32>)> {
+            match self.mode {
+                Mode::Normal => self.selected_story().map(|s| (s.id, vec![s.id])),
+                Mode::Comments => {
+                    let story = self.comments_story.as_ref()?;
+                    let comment = &self.comments.get(self.comments_selected)?.comment;
+                    Some((comment.id, vec![story.id, comment.id]))
+                }
+                _ => None,
+            }
+        }
+
+        // What `action` does to item `id`, given what the account has done to
+        // it already: upvoting twice takes the vote back, and so on.
+        fn item_action(&self, action: Action, id: u32) -> Option<ItemAction> {
+            let account = &self.account;
+            Some(match action {
+                Action::Upvote if account.is_upvoted(id) => ItemAction::Unvote,
+                Action::Upvote => ItemAction::Upvote,
+                Action::Favorite if account.is_favorite(id) => ItemAction::Unfavorite,
+                Action::Favorite => ItemAction::Favorite,
+                Action::HideStory if account.is_hidden(id) => ItemAction::Unhide,
+                Action::HideStory => ItemAction::Hide,
+                _ => return None,
+            })
+        }
+
+        fn cached_token(&self, id: u32, action: ItemAction) -> Option<String> {
+            self.item_links
+                .values()
+                .find_map(|links| links.token(id, action))
+                .map(str::to_string)
+        }
+
+        // Upvotes, favorites or hides the selected item as the logged-in
+        // user, or undoes it. HN wants a per-item token for each, taken from
+        // the item's page.
+        async fn run_account_action(&mut self, action: Action, terminal: &mut Tui) {
+            let Some(session) = self.account.session().cloned() else {
+                let hint = match self.keymap.key_label(self.mode, Action::CommandPalette) {
+                    Some(key) => format!("Log in to HN first (\"Log In to HN\" in {})", key),
+                    None => "Log in to HN first".to_string(),
+                };
+                return self.set_status_message(hint);
+            };
+            let Some((id, pages)) = self.account_target() else {
+                return;
+            };
+            let Some(mut item_action) = self.item_action(action, id) else {
+                return;
+            };
+            let mut token = self.cached_token(id, item_action);
+            for page in pages {
+                if token.is_some() || self.item_links.contains_key(&page) {
+                    continue;
+                }
+                self.set_status_message("Contacting HN...".to_string());
+                self.redraw(terminal);
+                let links = match self.hn_web.item_links(&session, page).await {
+                    Ok(links) => links,
+                    Err(e) => return self.report_error("HN page failed to load", e, action),
+                };
+                // The page may know better, e.g. after a vote in the browser.
+                if let Err(e) = self.account.sync(&links) {
+                    self.set_status_message(e);
+                }
+                self.item_links.insert(page, links);
+                item_action = self.item_action(action, id).unwrap_or(item_action);
+                token = self.cached_token(id, item_action);
+            }
+            let Some(token) = token else {
+                return self.set_status_message(
+                    "HN doesn't offer that here (e.g. own or archived items)".to_string(),
+                );
+            };
+            match self.hn_web.act(&session, id, item_action, &token).await {
+                Ok(()) => {
+                    // Tokens are per action, so the cached links are stale.
+                    self.item_links.retain(|_, links| !links.mentions(id));
+                    match self.account.record(id, item_action) {
+                        Ok(()) => self.set_status_message(item_action.done().to_string()),
+                        Err(e) => self.set_status_message(e),
+                    }
+                    if action == Action::HideStory {
+                        self.reapply_filters();
+                    }
+                }
+                Err(e) => self.report_error("HN action failed", e, action),
+            }
+        }
+
+        fn start_export(&mut self) {
+            let Some(story) = &self.comments_story else {
+                return;
+            };
+            let suggested = format!("hn-{}.md", story.id);
+            self.start_prompt(
+                PromptKind::ExportPath,
+                "Export to (.md/.json/.html, - for stdout)",
+            );
+            if let Some(prompt) = self.prompt.as_mut() {
+                prompt.input = suggested;
+            }
+        }
+
+        // Writes the open thread, as far as it's loaded, to the file or stdout
+        // named in the export prompt.
+        fn export_thread(&mut self, input: &str) {
+            let Some(story) = &self.comments_story else {
+                return;
+            };
+            let (format, target) = match ExportFormat::parse_target(input) {
+                Ok(parsed) => parsed,
+                Err(e) => return self.set_status_message(format!("Export failed: {}", e)),
+            };
+            let output = format.render(story, &self.comments);
+            let message = match target {
+                ExportTarget::Stdout => {
+                    self.stdout_exports.push(output);
+                    format!("{} export will be printed on exit", format.as_str())
+                }
+                ExportTarget::File { path, overwrite } => {
+                    let written = std::fs::OpenOptions::new()
+                        .write(true)
+                        .create(true)
+                        .truncate(true)
+                        .create_new(!overwrite)
+                        .open(&path)
+                        .and_then(|mut file| file.write_all(output.as_bytes()));
+                    match written {
+                        Ok(()) => format!("Exported {} to {}", format.as_str(), path.display()),
+                        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => format!(
+                            "Export failed: {} exists (end the path with ! to overwrite)",
+                            path.display()
+                        ),
+                        Err(e) => format!("Export failed: {}: {}", path.display(), e),
+                    }
+                }
+            };
+            self.set_status_message(message);
+        }
+
         pub fn open_comments(&mut self) {
-            if let Some(story) = self.stories.get(self.selected_index) {
+            if let Some(story) = self.selected_story().cloned() {
                 let hn_url = format!("https://news.ycombinator.com/item?id={}", story.id);
                 match open::that(&hn_url) {
-                    Ok(_) => self.set_status_message("Opened comments in browser".to_string()),
+                    Ok(_) => {
+                        self.set_status_message("Opened comments in browser".to_string());
+                        let result = self.history.mark_thread_opened(&story);
+                        self.report_history_error(result);
+                    }
                     Err(_) => self.set_status_message("Failed to open comments".to_string()),
                 }
             }
         }
 
-        pub async fn load_all_sections(
+        // Waits briefly for a key on a loading screen and reports whether it
+        // cancels the load: a key bound to quit or back where the load was
+        // started.
+        fn loading_cancelled(&mut self) -> Result<bool, AppError> {
+            if !event::poll(Duration::from_millis(50))? {
+                return Ok(false);
+            }
+            let Event::Key(key) = event::read()? else {
+                return Ok(false);
+            };
+            if key.kind != KeyEventKind::Press {
+                return Ok(false);
+            }
+            let resolution = self.keymap.resolve(self.mode, &mut self.pending_keys, key);
+            Ok(matches!(
+                resolution,
+                KeyResolution::Action(Action::Quit | Action::Back)
+            ))
+        }
+
+        // Waits for a spawned request with the status line up, polling for
+        // cancel keys as the loading screens do. `None` means it was
+        // cancelled and the task aborted.
+        async fn await_cancellable<T>(
             &mut self,
-            terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-        ) -> Result<(), Box<dyn Error + Send + Sync>> {
-            let mut matrix_rain = MatrixRain::new(terminal.size()?.width as usize);
-            let sections = vec![Section::Top, Section::Ask, Section::Show, Section::Jobs];
+            task: tokio::task::JoinHandle<T>,
+        ) -> Result<Option<T>, AppError> {
+            loop {
+                if self.signals.terminate_requested() || self.loading_cancelled()? {
+                    task.abort();
+                    return Ok(None);
+                }
+                if task.is_finished() {
+                    return Ok(Some(task.await?));
+                }
+                tokio::time::sleep(Duration::from_millis(16)).await;
+            }
+        }
+
+        pub async fn load_all_sections(&mut self, terminal: &mut Tui) -> Result<(), AppError> {
+            let mut matrix_rain = MatrixRain::new(terminal.size()?.width as usize, self.animation);
+            let sections = Section::FEEDS.to_vec();
 
             let futures: Vec<_> = sections
                 .into_iter()
                 .map(|section| tokio::spawn(async move { (section, fetch_stories(section).await) }))
                 .collect();
 
-            let start_time = std::time::Instant::now();
-
             loop {
-                terminal.draw(|f| matrix_rain.draw(f, f.area()))?;
+                terminal.draw(|f| matrix_rain.draw(f, f.area(), &self.theme))?;
                 matrix_rain.update();
 
-                if event::poll(Duration::from_millis(50))? {
-                    if let Event::Key(key) = event::read()? {
-                        if key.code == KeyCode::Char('q') {
-                            return Ok(());
-                        }
-                    }
+                if self.signals.terminate_requested() {
+                    return Ok(());
+                }
+
+                if self.loading_cancelled()? {
+                    return Ok(());
                 }
 
                 let all_complete = futures.iter().all(|f| f.is_finished());
 
                 if all_complete {
+                    // Failed sections and partly loaded ones, reported together.
+                    let mut problems = Vec::new();
+                    let mut retryable = false;
                     for future in futures {
                         match future.await {
-                            Ok((section, Ok(stories))) => {
-                                self.cached_stories.insert(section, stories);
+                            Ok((section, Ok(page))) => {
+                                if let Some(summary) = page.summary() {
+                                    problems.push(format!("{}: {}", section.as_str(), summary));
+                                }
+                                self.cached_stories.insert(section, page.stories);
                             }
                             Ok((section, Err(e))) => {
-                                self.set_status_message(format!(
+                                retryable |= e.is_retryable();
+                                problems.push(format!(
                                     "Failed to load {}: {}",
                                     section.as_str(),
                                     e
                                 ));
                             }
-                            Err(e) => {
-                                self.set_status_message(format!("Task error: {}", e));
-                            }
+                            Err(e) => problems.push(format!("Task error: {}", e)),
                         }
                     }
+                    if retryable {
+                        if let Some(key) = self.keymap.key_label(Mode::Normal, Action::RefreshAll) {
+                            problems.push(format!("press {} to retry", key));
+                        }
+                    }
+                    if !problems.is_empty() {
+                        self.set_status_message(problems.join("; "));
+                    }
 
-                    if let Some(stories) = self.cached_stories.get(&self.current_section) {
-                        self.set_stories(stories.clone());
+                    if let Some(stories) = self.section_stories(self.current_section) {
+                        self.set_stories(stories);
                     }
 
                     break;
                 }
 
-                if start_time.elapsed() > Duration::from_secs(30) {
-                    return Err("Timed out while loading sections".into());
-                }
-
                 tokio::time::sleep(Duration::from_millis(16)).await;
             }
 
             Ok(())
         }
 
-        pub async fn refresh_stories(
-            &mut self,
-            terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-        ) -> Result<(), Box<dyn Error + Send + Sync>> {
-            if let Some(cached) = self.cached_stories.get(&self.current_section) {
-                self.set_stories(cached.clone());
+        pub async fn refresh_stories(&mut self, terminal: &mut Tui) -> Result<(), AppError> {
+            if let Some(cached) = self.section_stories(self.current_section) {
+                tracing::debug!(section = self.current_section.as_str(), "story cache hit");
+                diagnostics::record_cache_hit();
+                self.set_stories(cached);
                 self.set_status_message(format!(
                     "Switched to {} stories",
                     self.current_section.as_str()
@@ -382,57 +871,40 @@ mod app_impl {
                 return Ok(());
             }
 
-            let mut matrix_rain = MatrixRain::new(terminal.size()?.width as usize);
+            let mut matrix_rain = MatrixRain::new(terminal.size()?.width as usize, self.animation);
             let section = self.current_section;
             let stories_future = tokio::spawn(async move { fetch_stories(section).await });
-            let start_time = std::time::Instant::now();
-
             loop {
-                terminal.draw(|f| matrix_rain.draw(f, f.area()))?;
+                terminal.draw(|f| matrix_rain.draw(f, f.area(), &self.theme))?;
                 matrix_rain.update();
 
-                if event::poll(Duration::from_millis(50))? {
-                    if let Event::Key(key) = event::read()? {
-                        if key.code == KeyCode::Char('q') {
-                            return Ok(());
-                        }
-                    }
+                if self.signals.terminate_requested() {
+                    return Ok(());
+                }
+
+                if self.loading_cancelled()? {
+                    return Ok(());
                 }
 
                 if stories_future.is_finished() {
                     match stories_future.await {
-                        Ok(Ok(stories)) => {
-                            self.set_stories(stories);
-                            self.set_status_message(format!(
-                                "Refreshed {} stories",
-                                section.as_str()
-                            ));
+                        Ok(Ok(page)) => {
+                            let message = match page.summary() {
+                                Some(summary) => {
+                                    format!("Refreshed {} stories ({})", section.as_str(), summary)
+                                }
+                                None => format!("Refreshed {} stories", section.as_str()),
+                            };
+                            self.cached_stories.insert(section, page.stories.clone());
+                            self.set_stories(page.stories);
+                            self.set_status_message(message);
                             break;
                         }
-                        Ok(Err(e)) => {
-                            return Err(Box::new(std::io::Error::other(format!(
-                                "Failed to fetch stories: {}",
-                                e
-                            )))
-                                as Box<dyn Error + Send + Sync>);
-                        }
-                        Err(e) => {
-                            return Err(Box::new(std::io::Error::other(format!(
-                                "Task join error: {}",
-                                e
-                            )))
-                                as Box<dyn Error + Send + Sync>);
-                        }
+                        Ok(Err(e)) => return Err(e.into()),
+                        Err(e) => return Err(e.into()),
                     }
                 }
 
-                if start_time.elapsed() > Duration::from_secs(30) {
-                    return Err(Box::new(std::io::Error::new(
-                        std::io::ErrorKind::TimedOut,
-                        "Timed out while refreshing stories",
-                    )) as Box<dyn Error + Send + Sync>);
-                }
-
                 tokio::time::sleep(Duration::from_millis(16)).await;
             }
 
@@ -440,92 +912,314 @@ mod app_impl {
         }
 
         pub fn ensure_story_visible(&mut self, height: usize) {
-            if self.selected_index < self.scroll_offset {
-                self.scroll_offset = self.selected_index;
-            } else if self.selected_index >= self.scroll_offset + height {
-                self.scroll_offset = self.selected_index - height + 1;
+            let row = self.selected_row();
+            if row < self.scroll_offset {
+                self.scroll_offset = row;
+            } else if row >= self.scroll_offset + height {
+                self.scroll_offset = row - height + 1;
+            }
+        }
+
+        pub async fn load_comments(&mut self, terminal: &mut Tui) -> Result<(), AppError> {
+            match self.selected_story() {
+                Some(story) => self.load_story_comments(story.clone(), terminal).await,
+                None => Ok(()),
+            }
+        }
+
+        // Reloads the thread currently shown in the comments view.
+        pub async fn reload_comments(&mut self, terminal: &mut Tui) -> Result<(), AppError> {
+            match self.comments_story.clone() {
+                Some(story) => self.load_story_comments(story, terminal).await,
+                None => Ok(()),
+            }
+        }
+
+        fn show_comments(&mut self, story: Story, comments: Vec<FlatComment>) {
+            if self.mode != Mode::Comments {
+                self.comments_return_mode = self.mode;
+            }
+            self.comments = comments;
+            self.comments_selected = 0;
+            self.comments_scroll = 0;
+            let thread = std::mem::take(&mut self.pending_thread);
+            if let Some(id) = self.pending_comment_focus.take() {
+                if !self.comments.iter().any(|fc| fc.comment.id == id) {
+                    self.splice_thread(thread);
+                }
+                if let Some(i) = self.comments.iter().position(|fc| fc.comment.id == id) {
+                    self.comments_selected = i;
+                }
             }
+            let new = self
+                .history
+                .record_comments(story.id, self.comments.iter().map(|fc| fc.comment.id));
+            // A refresh keeps what was already marked new in this visit.
+            let same_story = self
+                .comments_story
+                .as_ref()
+                .is_some_and(|s| s.id == story.id);
+            if same_story && self.mode == Mode::Comments {
+                self.new_comments.extend(new);
+                self.find_comment_matches();
+            } else {
+                self.new_comments = new;
+                self.collapsed.clear();
+                self.comment_query.clear();
+                self.comment_matches.clear();
+            }
+            let result = self.history.mark_thread_opened(&story);
+            self.report_history_error(result);
+            self.comments_story = Some(story);
+            self.mode = Mode::Comments;
         }
 
-        pub async fn load_comments(
+        // Inserts the comments of `thread` (top-level comment first) that
+        // weren't loaded, right under the deepest one that was.
+        fn splice_thread(&mut self, thread: Vec<Comment>) {
+            let mut insert_at = self.comments.len();
+            let mut loaded = 0;
+            for comment in &thread {
+                match self
+                    .comments
+                    .iter()
+                    .position(|fc| fc.comment.id == comment.id)
+                {
+                    Some(i) => {
+                        insert_at = i + 1;
+                        loaded += 1;
+                    }
+                    None => break,
+                }
+            }
+            let missing = thread
+                .into_iter()
+                .enumerate()
+                .skip(loaded)
+                .map(|(depth, comment)| FlatComment { comment, depth });
+            self.comments.splice(insert_at..insert_at, missing);
+        }
+
+        pub async fn load_story_comments(
             &mut self,
-            terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-        ) -> Result<(), Box<dyn Error + Send + Sync>> {
-            let story = match self.stories.get(self.selected_index) {
-                Some(s) => s.clone(),
-                None => return Ok(()),
-            };
-            self.comments_story_title = story.title.clone();
-            self.comments_story_id = story.id;
-
-            if story.kids.is_empty() {
-                self.comments = Vec::new();
-                self.comments_selected = 0;
-                self.comments_scroll = 0;
-                self.mode = Mode::Comments;
+            story: Story,
+            terminal: &mut Tui,
+        ) -> Result<(), AppError> {
+            if story.kids.is_empty() && !story.is_poll() {
+                self.poll_options.clear();
+                self.show_comments(story, Vec::new());
                 return Ok(());
             }
 
-            let mut matrix_rain = MatrixRain::new(terminal.size()?.width as usize);
+            let mut matrix_rain = MatrixRain::new(terminal.size()?.width as usize, self.animation);
             let story_clone = story.clone();
-            let comments_future = tokio::spawn(async move { fetch_comments(&story_clone).await });
-            let start_time = std::time::Instant::now();
-
+            let comments_future = tokio::spawn(async move {
+                let comments = fetch_comments(&story_clone).await?;
+                let options = fetch_poll_options(&story_clone).await;
+                Ok::<_, ApiError>((comments, options))
+            });
             loop {
-                terminal.draw(|f| matrix_rain.draw(f, f.area()))?;
+                terminal.draw(|f| matrix_rain.draw(f, f.area(), &self.theme))?;
                 matrix_rain.update();
 
-                if event::poll(Duration::from_millis(50))? {
-                    if let Event::Key(key) = event::read()? {
-                        if key.code == KeyCode::Char('q') || key.code == KeyCode::Esc {
-                            return Ok(());
-                        }
-                    }
+                if self.signals.terminate_requested() {
+                    return Ok(());
+                }
+
+                if self.loading_cancelled()? {
+                    self.pending_comment_focus = None;
+                    self.pending_thread.clear();
+                    return Ok(());
                 }
 
                 if comments_future.is_finished() {
                     match comments_future.await {
-                        Ok(Ok(comments)) => {
-                            self.comments = comments;
-                            self.comments_selected = 0;
-                            self.comments_scroll = 0;
-                            self.mode = Mode::Comments;
-                            break;
-                        }
-                        Ok(Err(e)) => {
-                            self.set_status_message(format!("Failed to load comments: {}", e));
-                            break;
-                        }
-                        Err(e) => {
-                            self.set_status_message(format!("Task error: {}", e));
+                        Ok(Ok((comments, options))) => {
+                            self.poll_options = options;
+                            self.show_comments(story, comments);
                             break;
                         }
+                        Ok(Err(e)) => return Err(e.into()),
+                        Err(e) => return Err(e.into()),
                     }
                 }
 
-                if start_time.elapsed() > Duration::from_secs(30) {
-                    self.set_status_message("Timed out loading comments".to_string());
-                    break;
-                }
-
                 tokio::time::sleep(Duration::from_millis(16)).await;
             }
 
             Ok(())
         }
 
+        // For each comment, whether it is folded away inside a collapsed
+        // ancestor.
+        pub fn hidden_comments(&self) -> Vec<bool> {
+            let mut collapsed_depth: Option<usize> = None;
+            self.comments
+                .iter()
+                .map(|fc| {
+                    if collapsed_depth.is_some_and(|d| fc.depth > d) {
+                        return true;
+                    }
+                    collapsed_depth = self.collapsed.contains(&fc.comment.id).then_some(fc.depth);
+                    false
+                })
+                .collect()
+        }
+
+        // Number of replies below comment `i` in the loaded thread.
+        pub fn reply_count(&self, i: usize) -> usize {
+            let depth = self.comments[i].depth;
+            self.comments[i + 1..]
+                .iter()
+                .take_while(|fc| fc.depth > depth)
+                .count()
+        }
+
+        // The first comment after (or before) the selection, wrapping around
+        // the thread, for which `matches` holds.
+        fn find_comment(&self, forward: bool, matches: impl Fn(usize) -> bool) -> Option<usize> {
+            let len = self.comments.len();
+            (1..=len)
+                .map(|step| {
+                    if forward {
+                        (self.comments_selected + step) % len
+                    } else {
+                        (self.comments_selected + len - step % len) % len
+                    }
+                })
+                .find(|&i| matches(i))
+        }
+
+        // Selects comment `i`, unfolding any collapsed ancestors so it can be
+        // seen.
+        fn select_comment(&mut self, i: usize) {
+            let mut depth = self.comments[i].depth;
+            for fc in self.comments[..i].iter().rev() {
+                if fc.depth < depth {
+                    self.collapsed.remove(&fc.comment.id);
+                    depth = fc.depth;
+                }
+            }
+            self.comments_selected = i;
+        }
+
         pub fn next_comment(&mut self) {
-            if !self.comments.is_empty() {
-                self.comments_selected = (self.comments_selected + 1) % self.comments.len();
+            let hidden = self.hidden_comments();
+            if let Some(i) = self.find_comment(true, |i| !hidden[i]) {
+                self.comments_selected = i;
             }
         }
 
         pub fn previous_comment(&mut self) {
-            if !self.comments.is_empty() {
-                self.comments_selected = self
-                    .comments_selected
-                    .checked_sub(1)
-                    .unwrap_or(self.comments.len() - 1);
+            let hidden = self.hidden_comments();
+            if let Some(i) = self.find_comment(false, |i| !hidden[i]) {
+                self.comments_selected = i;
+            }
+        }
+
+        fn last_visible_comment(&mut self) {
+            let hidden = self.hidden_comments();
+            if let Some(i) = hidden.iter().rposition(|&h| !h) {
+                self.comments_selected = i;
+            }
+        }
+
+        fn toggle_collapse(&mut self) {
+            let Some(fc) = self.comments.get(self.comments_selected) else {
+                return;
+            };
+            let id = fc.comment.id;
+            if self.reply_count(self.comments_selected) == 0 {
+                self.set_status_message("No replies to collapse".to_string());
+            } else if !self.collapsed.remove(&id) {
+                self.collapsed.insert(id);
+            }
+        }
+
+        // Selects the next (or previous) comment that is new since the last
+        // visit, wrapping around the thread.
+        fn jump_to_new_comment(&mut self, forward: bool) {
+            if self.new_comments.is_empty() || self.comments.is_empty() {
+                self.set_status_message("No new comments".to_string());
+                return;
+            }
+            let new = &self.new_comments;
+            let comments = &self.comments;
+            if let Some(i) = self.find_comment(forward, |i| new.contains(&comments[i].comment.id)) {
+                self.select_comment(i);
+            }
+        }
+
+        fn start_comment_search(&mut self) {
+            self.mode = Mode::CommentSearch;
+            self.comment_query.clear();
+            self.comment_matches.clear();
+        }
+
+        // Collects the comments whose author or text contains the query,
+        // ignoring ASCII case.
+        fn find_comment_matches(&mut self) {
+            let query = self.comment_query.to_ascii_lowercase();
+            self.comment_matches = if query.is_empty() {
+                Vec::new()
+            } else {
+                self.comments
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, fc)| {
+                        let text = fc
+                            .comment
+                            .text
+                            .as_deref()
+                            .map(strip_html)
+                            .unwrap_or_default();
+                        fc.comment.by.to_ascii_lowercase().contains(&query)
+                            || text.to_ascii_lowercase().contains(&query)
+                    })
+                    .map(|(i, _)| i)
+                    .collect()
+            };
+        }
+
+        // Re-runs the search as it's typed and moves to the first match at or
+        // after the selection.
+        fn update_comment_search(&mut self) {
+            self.find_comment_matches();
+            let selected = self.comments_selected;
+            let first = self
+                .comment_matches
+                .iter()
+                .find(|&&i| i >= selected)
+                .or(self.comment_matches.first())
+                .copied();
+            if let Some(i) = first {
+                self.select_comment(i);
+            }
+        }
+
+        fn jump_to_match(&mut self, forward: bool) {
+            if self.comment_matches.is_empty() {
+                let message = if self.comment_query.is_empty() {
+                    "No search in this thread".to_string()
+                } else {
+                    format!("No matches for \"{}\"", self.comment_query)
+                };
+                self.set_status_message(message);
+                return;
+            }
+            let matches = &self.comment_matches;
+            if let Some(i) = self.find_comment(forward, |i| matches.contains(&i)) {
+                self.select_comment(i);
+                let n = self
+                    .comment_matches
+                    .iter()
+                    .position(|&m| m == i)
+                    .unwrap_or(0);
+                self.set_status_message(format!(
+                    "Match {} of {}",
+                    n + 1,
+                    self.comment_matches.len()
+                ));
             }
         }
 
@@ -537,20 +1231,25 @@ mod app_impl {
             }
         }
 
+        // Applies the filter-bar query. While the query doesn't parse, the
+        // previous results stay and the error is shown in the search box.
         pub fn filter_stories(&mut self) {
-            if self.search_query.is_empty() {
+            let query = match StoryQuery::parse(&self.search_query) {
+                Ok(query) => query,
+                Err(e) => {
+                    self.search_error = Some(e);
+                    return;
+                }
+            };
+            self.search_error = None;
+            if query.is_empty() {
                 self.filtered_stories = (0..self.stories.len()).collect();
             } else {
                 self.filtered_stories = self
                     .stories
                     .iter()
                     .enumerate()
-                    .filter(|(_, story)| {
-                        story
-                            .title
-                            .to_lowercase()
-                            .contains(&self.search_query.to_lowercase())
-                    })
+                    .filter(|(_, story)| query.matches(story))
                     .map(|(i, _)| i)
                     .collect();
             }
@@ -558,337 +1257,752 @@ mod app_impl {
                 self.selected_index = *self.filtered_stories.first().unwrap_or(&0);
             }
         }
-    }
-}
 
-use app::App;
-use hn_api::get_claude_summary;
-use types::{Mode, Section};
+        pub async fn switch_section(&mut self, section: Section, terminal: &mut Tui) {
+            if self.current_section == section {
+                return;
+            }
+            // A filter belongs to the list it was typed over.
+            self.cancel_search();
+            self.current_section = section;
+            if let Err(e) = self.refresh_stories(terminal).await {
+                self.report_error("Failed to load stories", e, Action::Refresh);
+            }
+        }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+        pub async fn summarize_current_story(&mut self, terminal: &mut Tui) {
+            if let Some(story) = self.selected_story() {
+                let prompt = summary_prompt(story);
+                self.set_status_message("Summarizing...".to_string());
+                self.redraw(terminal);
+                match get_claude_summary(&prompt).await {
+                    Ok(summary) => {
+                        self.claude_summary = Some(summary);
+                        self.mode = Mode::Summary;
+                    }
+                    Err(e) => self.report_error("Failed to get summary", e, Action::Summarize),
+                }
+            }
+        }
 
-    let mut app = App::new();
+        pub fn reply_to_comment(&mut self) {
+            if let Some(fc) = self.comments.get(self.comments_selected) {
+                let url = format!(
+                    "https://news.ycombinator.com/reply?id={}&goto=item%3Fid%3D{}%23{}",
+                    fc.comment.id,
+                    self.comments_story.as_ref().map_or(0, |s| s.id),
+                    fc.comment.id
+                );
+                match open::that(&url) {
+                    Ok(_) => self.set_status_message("Opened reply page in browser".to_string()),
+                    Err(_) => self.set_status_message("Failed to open reply page".to_string()),
+                }
+            }
+        }
 
-    if let Err(e) = app.load_all_sections(&mut terminal).await {
-        app.set_status_message(format!("Failed to load sections: {}", e));
-    }
+        pub fn open_selected_comment(&mut self) {
+            if let Some(fc) = self.comments.get(self.comments_selected) {
+                let url = format!("https://news.ycombinator.com/item?id={}", fc.comment.id);
+                match open::that(&url) {
+                    Ok(_) => self.set_status_message("Opened comment in browser".to_string()),
+                    Err(_) => self.set_status_message("Failed to open comment".to_string()),
+                }
+            }
+        }
 
-    loop {
-        terminal.draw(|f| ui::draw_ui(f, &mut app))?;
+        fn close_menu(&mut self) {
+            self.show_menu = false;
+            self.mode = Mode::Normal;
+        }
 
-        if let Event::Key(key) = event::read()? {
-            match app.mode {
-                Mode::Normal => match key.code {
-                    KeyCode::Char('q') => break,
-                    KeyCode::Char('c') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
-                        break
-                    }
-                    KeyCode::Char('k') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
-                        app.mode = Mode::CommandPalette;
-                        app.command_palette.search_query.clear();
-                        app.command_palette.filter_commands();
-                    }
-                    KeyCode::Char('/') => {
-                        app.mode = Mode::Search;
-                        app.search_query.clear();
-                        app.filtered_stories = (0..app.stories.len()).collect();
-                    }
-                    KeyCode::Char('j') | KeyCode::Down => app.next_story(),
-                    KeyCode::Char('k') | KeyCode::Up => app.previous_story(),
-                    KeyCode::Char('R') => {
-                        if let Err(e) = app.load_all_sections(&mut terminal).await {
-                            app.set_status_message(format!(
-                                "Failed to refresh all sections: {}",
-                                e
-                            ));
-                        }
+        // Opens the filter bar, picking up a committed filter for editing.
+        fn start_search(&mut self) {
+            self.mode = Mode::Search;
+            self.filter_stories();
+        }
+
+        // Leaves the filter bar with the filtered list still shown, so every
+        // story action works on it. Esc in the list clears it.
+        fn commit_filter(&mut self) {
+            if let Some(e) = &self.search_error {
+                self.set_status_message(format!("Invalid filter: {}", e));
+                return;
+            }
+            self.mode = Mode::Normal;
+            if self.search_query.trim().is_empty() {
+                self.search_query.clear();
+            }
+        }
+
+        fn cancel_search(&mut self) {
+            self.mode = Mode::Normal;
+            self.search_query.clear();
+            self.search_error = None;
+            self.filtered_stories = (0..self.stories.len()).collect();
+        }
+
+        // Draws the current screen once, e.g. to show a status message before
+        // waiting on the network.
+        fn redraw(&mut self, terminal: &mut Tui) {
+            let _ = terminal.draw(|f| crate::ui::draw_ui(f, self));
+        }
+
+        async fn run_remote_search(&mut self, terminal: &mut Tui) {
+            let search = &self.remote_search;
+            let query = match SearchQuery::parse(&search.input, search.kind, search.sort) {
+                Ok(query) => query,
+                Err(e) => return self.set_status_message(e),
+            };
+            self.set_status_message("Searching...".to_string());
+            self.redraw(terminal);
+            let client = self.remote_search.client.clone();
+            let task = tokio::spawn(async move { client.search(&query).await });
+            match self.await_cancellable(task).await {
+                Ok(Some(Ok(results))) => {
+                    let search = &mut self.remote_search;
+                    search.last_run = Some((search.input.clone(), search.kind, search.sort));
+                    search.total = results.total;
+                    search.results = results.hits;
+                    search.selected = 0;
+                    search.scroll = 0;
+                    self.set_status_message(format!("{} results", self.remote_search.total));
+                }
+                Ok(Some(Err(e))) => self.report_error("Search failed", e, Action::Select),
+                Ok(None) => self.set_status_message("Search cancelled".to_string()),
+                Err(e) => self.report_error("Search failed", e, Action::Select),
+            }
+        }
+
+        // Opens the selected search result's thread in the comments view,
+        // selecting the comment itself for comment results.
+        async fn open_search_result(&mut self, terminal: &mut Tui) {
+            let Some(hit) = self.remote_search.results.get(self.remote_search.selected) else {
+                return;
+            };
+            // Comments go through `open_item` so the thread down to them is
+            // spliced in even when it's too deep to load in full.
+            let Some(id) = hit.id().or(hit.story_id) else {
+                return self.set_status_message("Result has no story to open".to_string());
+            };
+            self.open_item(id, terminal).await;
+        }
+
+        // Fetches an item by id and opens its thread in the comments view,
+        // at the comment itself if it's a comment.
+        pub async fn open_item(&mut self, id: u32, terminal: &mut Tui) {
+            self.set_status_message("Loading item...".to_string());
+            self.redraw(terminal);
+            let story = match self.await_cancellable(tokio::spawn(resolve_item(id))).await {
+                Ok(Some(Ok(Some(ItemTarget::Story(story))))) => Ok(story),
+                Ok(Some(Ok(Some(ItemTarget::Comment { story, thread })))) => {
+                    self.pending_comment_focus = Some(id);
+                    self.pending_thread = thread;
+                    Ok(story)
+                }
+                Ok(Some(Ok(None))) => Err(None),
+                Ok(Some(Err(e))) => Err(Some(e.into())),
+                Ok(None) => return self.set_status_message("Cancelled".to_string()),
+                Err(e) => Err(Some(e)),
+            };
+            match story {
+                Ok(story) => {
+                    if let Err(e) = self.load_story_comments(story, terminal).await {
+                        self.report_error("Failed to load comments", e, Action::GoTo);
                     }
-                    KeyCode::Char('r') => {
-                        if let Err(e) = app.refresh_stories(&mut terminal).await {
-                            app.set_status_message(format!("Refresh failed: {}", e));
-                        }
+                }
+                Err(None) => self.set_status_message(format!("Item {} not found", id)),
+                Err(Some(e)) => self.report_error("Failed to load item", e, Action::GoTo),
+            }
+            self.pending_comment_focus = None;
+            self.pending_thread.clear();
+        }
+
+        fn start_go_to(&mut self) {
+            self.start_prompt(PromptKind::GoTo, "Go to (item id or HN link)");
+        }
+
+        // Opens whatever a pasted id or HN link points at.
+        async fn go_to(&mut self, input: &str, terminal: &mut Tui) {
+            match HnLink::parse(input) {
+                Some(HnLink::Item(id)) => self.open_item(id, terminal).await,
+                Some(HnLink::User(name)) => self.search_user(&name, terminal).await,
+                Some(HnLink::Site(site)) => self.search_site(&site, terminal).await,
+                None if input.trim().is_empty() => {}
+                None => {
+                    self.set_status_message(format!("Not an item id or HN link: {}", input.trim()))
+                }
+            }
+        }
+
+        // Runs a filter-bar query and keeps it, as if typed and committed.
+        pub fn apply_filter(&mut self, query: &str) {
+            self.search_query = query.to_string();
+            self.filter_stories();
+            self.commit_filter();
+        }
+
+        // Opens the HN search screen on a user's stories, newest first.
+        pub async fn search_user(&mut self, name: &str, terminal: &mut Tui) {
+            self.remote_search.input = format!("by:{}", name);
+            self.remote_search.kind = SearchKind::Stories;
+            self.remote_search.sort = SearchSort::Date;
+            self.mode = Mode::RemoteSearch;
+            self.run_remote_search(terminal).await;
+        }
+
+        // Opens the HN search screen on stories linking to a domain.
+        async fn search_site(&mut self, site: &str, terminal: &mut Tui) {
+            self.remote_search.input = format!("site:{}", site);
+            self.remote_search.kind = SearchKind::Stories;
+            self.remote_search.sort = SearchSort::Date;
+            self.mode = Mode::RemoteSearch;
+            self.run_remote_search(terminal).await;
+        }
+
+        // Runs the selected palette entry as if its action had been triggered
+        // from the mode the palette was opened in.
+        async fn run_palette_command(&mut self, terminal: &mut Tui) -> bool {
+            let action = self
+                .command_palette
+                .get_selected_command()
+                .map(|entry| entry.action);
+            self.mode = self.command_palette.context;
+            self.command_palette.search_query.clear();
+            match action {
+                Some(action) => Box::pin(self.handle_action(action, terminal)).await,
+                None => true,
+            }
+        }
+
+        // Runs `action` in the context of the current mode. Returns `false` when
+        // the app should exit.
+        pub async fn handle_action(&mut self, action: Action, terminal: &mut Tui) -> bool {
+            match (self.mode, action) {
+                (_, Action::Quit) => return false,
+                (_, Action::Suspend) => {
+                    if let Err(e) = terminal::suspend(terminal) {
+                        self.set_status_message(format!("Failed to suspend: {}", e));
                     }
-                    KeyCode::Char('T') => {
-                        if app.current_section != Section::Top {
-                            app.current_section = Section::Top;
-                            if let Err(e) = app.refresh_stories(&mut terminal).await {
-                                app.set_status_message(format!("Failed to load stories: {}", e));
-                            }
-                        }
+                }
+                (_, Action::ToggleDebug) => self.show_debug = !self.show_debug,
+                (mode, Action::CommandPalette) if mode != Mode::CommandPalette => {
+                    self.command_palette.open(mode);
+                    self.mode = Mode::CommandPalette;
+                }
+
+                (Mode::Normal, Action::Search) => self.start_search(),
+                (Mode::Normal, Action::RemoteSearch) => self.mode = Mode::RemoteSearch,
+                (Mode::Normal, Action::MoveDown) => self.next_story(),
+                (Mode::Normal, Action::MoveUp) => self.previous_story(),
+                (Mode::Normal, Action::MoveTop) => self.select_row(0),
+                (Mode::Normal, Action::MoveBottom) => {
+                    self.select_row(self.filtered_stories.len().saturating_sub(1))
+                }
+                (Mode::Normal, Action::Back) if !self.search_query.is_empty() => {
+                    self.cancel_search();
+                    self.set_status_message("Filter cleared".to_string());
+                }
+                (Mode::Normal, Action::RefreshAll) => {
+                    if let Err(e) = self.load_all_sections(terminal).await {
+                        self.report_error("Failed to refresh all sections", e, Action::RefreshAll);
                     }
-                    KeyCode::Char('A') => {
-                        if app.current_section != Section::Ask {
-                            app.current_section = Section::Ask;
-                            if let Err(e) = app.refresh_stories(&mut terminal).await {
-                                app.set_status_message(format!("Failed to load stories: {}", e));
-                            }
-                        }
+                }
+                (Mode::Normal, Action::Refresh) => {
+                    if let Err(e) = self.refresh_stories(terminal).await {
+                        self.report_error("Refresh failed", e, Action::Refresh);
                     }
-                    KeyCode::Char('S') => {
-                        if app.current_section != Section::Show {
-                            app.current_section = Section::Show;
-                            if let Err(e) = app.refresh_stories(&mut terminal).await {
-                                app.set_status_message(format!("Failed to load stories: {}", e));
-                            }
-                        }
+                }
+                (Mode::Normal, Action::SectionTop) => {
+                    self.switch_section(Section::Top, terminal).await
+                }
+                (Mode::Normal, Action::SectionAsk) => {
+                    self.switch_section(Section::Ask, terminal).await
+                }
+                (Mode::Normal, Action::SectionShow) => {
+                    self.switch_section(Section::Show, terminal).await
+                }
+                (Mode::Normal, Action::SectionJobs) => {
+                    self.switch_section(Section::Jobs, terminal).await
+                }
+                (Mode::Normal, Action::PreviousSection) => {
+                    let section = self.current_section.previous();
+                    self.switch_section(section, terminal).await;
+                }
+                (Mode::Normal, Action::NextSection) => {
+                    let section = self.current_section.next();
+                    self.switch_section(section, terminal).await;
+                }
+                (Mode::Normal, Action::OpenStory) => self.open_current_story(),
+                (Mode::Normal, Action::OpenMenu) => {
+                    self.show_menu = true;
+                    self.mode = Mode::Menu;
+                    self.menu_index = 0;
+                }
+                (Mode::Normal, Action::SectionSaved) => {
+                    self.switch_section(Section::Saved, terminal).await
+                }
+                (Mode::Normal | Mode::Comments, Action::Bookmark) => self.bookmark_selected(),
+                (Mode::Normal | Mode::Comments, Action::MuteUser) => self.mute_selected_user(),
+                (Mode::Normal | Mode::Comments, Action::ToggleHidden) => self.toggle_show_hidden(),
+                (Mode::Normal, Action::MuteDomain) => self.mute_selected_domain(),
+                (Mode::Normal, Action::CycleSort) => self.cycle_sort(),
+                (Mode::Normal, Action::MutePattern) => {
+                    self.start_prompt(PromptKind::MutePattern, "Hide titles matching (regex)")
+                }
+                (Mode::Normal, Action::OpenComments) => {
+                    // Comment bookmarks open their thread at the saved comment.
+                    if self.current_section == Section::Saved {
+                        self.pending_comment_focus = self
+                            .bookmarks
+                            .items
+                            .get(self.selected_index)
+                            .and_then(|b| b.comment_id());
                     }
-                    KeyCode::Char('J') => {
-                        if app.current_section != Section::Jobs {
-                            app.current_section = Section::Jobs;
-                            if let Err(e) = app.refresh_stories(&mut terminal).await {
-                                app.set_status_message(format!("Failed to load stories: {}", e));
-                            }
-                        }
+                    if let Err(e) = self.load_comments(terminal).await {
+                        self.report_error("Failed to load comments", e, Action::OpenComments);
                     }
-                    KeyCode::Enter => app.open_current_story(),
-                    KeyCode::Char('o') => {
-                        app.show_menu = true;
-                        app.mode = Mode::Menu;
-                        app.menu_index = 0;
+                }
+                (Mode::Normal, Action::OpenCommentsInBrowser) => self.open_comments(),
+                (Mode::Normal | Mode::Menu, Action::Summarize) => {
+                    self.summarize_current_story(terminal).await
+                }
+
+                (Mode::Menu, Action::Back) => self.close_menu(),
+                (Mode::Menu, Action::Select) => match self.menu_index {
+                    0 => self.summarize_current_story(terminal).await,
+                    1 => {
+                        self.open_current_story();
+                        self.close_menu();
                     }
-                    KeyCode::Char('c') => {
-                        if let Err(e) = app.load_comments(&mut terminal).await {
-                            app.set_status_message(format!("Failed to load comments: {}", e));
-                        }
+                    _ => self.close_menu(),
+                },
+                (Mode::Menu, Action::OpenStory) => {
+                    self.open_current_story();
+                    self.close_menu();
+                }
+                (Mode::Menu, Action::MoveDown) => self.menu_index = (self.menu_index + 1) % 3,
+                (Mode::Menu, Action::MoveUp) => {
+                    self.menu_index = self.menu_index.checked_sub(1).unwrap_or(2)
+                }
+
+                (Mode::Summary, Action::Back) => {
+                    self.claude_summary = None;
+                    self.show_menu = false;
+                    self.mode = Mode::Normal;
+                }
+
+                (Mode::Comments, Action::Back) => self.mode = self.comments_return_mode,
+                (Mode::Comments, Action::MoveDown) => self.next_comment(),
+                (Mode::Comments, Action::MoveUp) => self.previous_comment(),
+                (Mode::Comments, Action::MoveTop) => self.comments_selected = 0,
+                (Mode::Comments, Action::MoveBottom) => self.last_visible_comment(),
+                (Mode::Comments, Action::ToggleCollapse) => self.toggle_collapse(),
+                (Mode::Comments, Action::Search) => self.start_comment_search(),
+                (Mode::Comments, Action::NextMatch) => self.jump_to_match(true),
+                (Mode::Comments, Action::PreviousMatch) => self.jump_to_match(false),
+
+                (Mode::CommentSearch, Action::Back) => {
+                    self.comment_query.clear();
+                    self.comment_matches.clear();
+                    self.mode = Mode::Comments;
+                }
+                (Mode::CommentSearch, Action::Select) => self.mode = Mode::Comments,
+                (Mode::CommentSearch, Action::DeleteChar) => {
+                    self.comment_query.pop();
+                    self.update_comment_search();
+                }
+                (Mode::CommentSearch, Action::NextMatch) => self.jump_to_match(true),
+                (Mode::CommentSearch, Action::PreviousMatch) => self.jump_to_match(false),
+                (Mode::Comments, Action::NextNewComment) => self.jump_to_new_comment(true),
+                (Mode::Comments, Action::PreviousNewComment) => self.jump_to_new_comment(false),
+                (Mode::Comments, Action::Reply) => self.reply_to_comment(),
+                (Mode::Comments, Action::Export) => self.start_export(),
+                (Mode::Normal | Mode::Comments, Action::GoTo) => self.start_go_to(),
+                (Mode::Normal | Mode::Comments, Action::Upvote | Action::Favorite)
+                | (Mode::Normal, Action::HideStory) => {
+                    self.run_account_action(action, terminal).await
+                }
+                (Mode::Normal | Mode::Comments, Action::LogIn) => self.start_log_in(),
+                (Mode::Normal | Mode::Comments, Action::LogOut) => self.log_out(),
+                (Mode::Comments, Action::Refresh) => {
+                    if let Err(e) = self.reload_comments(terminal).await {
+                        self.report_error("Failed to refresh comments", e, Action::Refresh);
                     }
-                    KeyCode::Char('C') => {
-                        app.open_comments();
+                }
+                (Mode::Comments, Action::OpenStory) => self.open_selected_comment(),
+
+                (Mode::CommandPalette, Action::Back) => {
+                    self.mode = self.command_palette.context;
+                    self.command_palette.search_query.clear();
+                }
+                (Mode::CommandPalette, Action::MoveDown) => self.command_palette.next_command(),
+                (Mode::CommandPalette, Action::MoveUp) => self.command_palette.previous_command(),
+                (Mode::CommandPalette, Action::DeleteChar) => {
+                    self.command_palette.search_query.pop();
+                    self.command_palette.filter_commands();
+                }
+                (Mode::CommandPalette, Action::Select) => {
+                    return self.run_palette_command(terminal).await
+                }
+
+                (Mode::Search, Action::Back) => self.cancel_search(),
+                (Mode::Search, Action::DeleteChar) => {
+                    self.search_query.pop();
+                    self.filter_stories();
+                }
+                (Mode::Search, Action::CommitFilter) => self.commit_filter(),
+                (Mode::Search, Action::MoveDown) => self.next_story(),
+                (Mode::Search, Action::MoveUp) => self.previous_story(),
+
+                (Mode::RemoteSearch, Action::Back) => self.mode = Mode::Normal,
+                (Mode::RemoteSearch, Action::Select) => {
+                    if self.remote_search.is_stale() {
+                        self.run_remote_search(terminal).await;
+                    } else {
+                        self.open_search_result(terminal).await;
                     }
-                    KeyCode::Char('h') => {
-                        app.current_section = match app.current_section {
-                            Section::Top => Section::Jobs,
-                            Section::Jobs => Section::Show,
-                            Section::Show => Section::Ask,
-                            Section::Ask => Section::Top,
-                        };
-                        if let Err(e) = app.refresh_stories(&mut terminal).await {
-                            app.set_status_message(format!("Failed to load stories: {}", e));
+                }
+                (Mode::RemoteSearch, Action::OpenStory) => self.open_search_result(terminal).await,
+                (Mode::RemoteSearch, Action::MoveDown) => self.remote_search.next(),
+                (Mode::RemoteSearch, Action::MoveUp) => self.remote_search.previous(),
+                (Mode::RemoteSearch, Action::DeleteChar) => {
+                    self.remote_search.input.pop();
+                }
+                (Mode::RemoteSearch, Action::ToggleSearchKind) => {
+                    self.remote_search.kind = self.remote_search.kind.toggle();
+                }
+                (Mode::RemoteSearch, Action::ToggleSearchSort) => {
+                    self.remote_search.sort = self.remote_search.sort.toggle();
+                }
+
+                (Mode::Prompt, Action::Back) => self.cancel_prompt(),
+                (Mode::Prompt, Action::Select) => {
+                    let submitted = self.prompt.as_ref().map(|p| (p.kind, p.input.clone()));
+                    self.submit_prompt();
+                    match submitted {
+                        Some((PromptKind::GoTo, input)) => self.go_to(&input, terminal).await,
+                        Some((PromptKind::LoginPassword, password)) => {
+                            self.log_in(&password, terminal).await
                         }
+                        _ => {}
                     }
-                    KeyCode::Char('l') => {
-                        app.current_section = match app.current_section {
-                            Section::Top => Section::Ask,
-                            Section::Ask => Section::Show,
-                            Section::Show => Section::Jobs,
-                            Section::Jobs => Section::Top,
-                        };
-                        if let Err(e) = app.refresh_stories(&mut terminal).await {
-                            app.set_status_message(format!("Failed to load stories: {}", e));
-                        }
+                }
+                (Mode::Prompt, Action::DeleteChar) => {
+                    if let Some(prompt) = self.prompt.as_mut() {
+                        prompt.input.pop();
                     }
-                    _ => {}
-                },
-                Mode::Menu => match key.code {
-                    KeyCode::Esc => {
-                        app.show_menu = false;
-                        app.mode = Mode::Normal;
-                    }
-                    KeyCode::Enter => match app.menu_index {
-                        0 => {
-                            if let Some(story) = app.stories.get(app.selected_index) {
-                                let text = story.text.clone().unwrap_or_default();
-                                match get_claude_summary(&text).await {
-                                    Ok(summary) => {
-                                        app.claude_summary = Some(summary);
-                                        app.mode = Mode::Summary;
-                                    }
-                                    Err(e) => {
-                                        app.set_status_message(format!(
-                                            "Failed to get summary: {}",
-                                            e
-                                        ));
-                                    }
-                                }
-                            }
-                        }
-                        1 => {
-                            app.open_current_story();
-                            app.show_menu = false;
-                            app.mode = Mode::Normal;
-                        }
-                        _ => {
-                            app.show_menu = false;
-                            app.mode = Mode::Normal;
+                }
+
+                _ => {}
+            }
+            true
+        }
+
+        // Records a left click and reports whether it completes a double-click.
+        fn register_click(&mut self, column: u16, row: u16) -> bool {
+            let now = std::time::Instant::now();
+            let double = self.last_click.is_some_and(|(at, c, r)| {
+                c == column && r == row && now.duration_since(at) <= DOUBLE_CLICK
+            });
+            // A double-click consumes the click so a third one starts over.
+            self.last_click = if double {
+                None
+            } else {
+                Some((now, column, row))
+            };
+            double
+        }
+
+        // Maps a row inside `area` to an item index, given the index of the
+        // first visible item.
+        fn row_in(area: Rect, offset: usize, column: u16, row: u16) -> Option<usize> {
+            area.contains(Position::new(column, row))
+                .then(|| offset + (row - area.y) as usize)
+        }
+
+        // Mouse wheel moves the selection, clicks select and double-clicks open.
+        // Returns `false` when the app should exit.
+        pub async fn handle_mouse(&mut self, mouse: MouseEvent, terminal: &mut Tui) -> bool {
+            let (column, row) = (mouse.column, mouse.row);
+            match mouse.kind {
+                MouseEventKind::ScrollDown => {
+                    return self.handle_action(Action::MoveDown, terminal).await
+                }
+                MouseEventKind::ScrollUp => {
+                    return self.handle_action(Action::MoveUp, terminal).await
+                }
+                MouseEventKind::Down(MouseButton::Left) => {}
+                _ => return true,
+            }
+            let double = self.register_click(column, row);
+            let hits = &self.hit_map;
+
+            match self.mode {
+                Mode::CommandPalette => {
+                    if !hits.palette.contains(Position::new(column, row)) {
+                        return self.handle_action(Action::Back, terminal).await;
+                    }
+                    let offset = hits.palette_offset;
+                    if let Some(i) = Self::row_in(hits.palette_list, offset, column, row) {
+                        if i < self.command_palette.matches.len() {
+                            self.command_palette.selected_index = i;
+                            return self.handle_action(Action::Select, terminal).await;
                         }
-                    },
-                    KeyCode::Char('j') | KeyCode::Down => {
-                        app.menu_index = (app.menu_index + 1) % 3;
                     }
-                    KeyCode::Char('k') | KeyCode::Up => {
-                        app.menu_index = app.menu_index.checked_sub(1).unwrap_or(2);
+                }
+                Mode::Menu => match Self::row_in(hits.menu, 0, column, row) {
+                    Some(i) if i < 3 => {
+                        self.menu_index = i;
+                        return self.handle_action(Action::Select, terminal).await;
                     }
-                    _ => {}
+                    Some(_) => {}
+                    None => return self.handle_action(Action::Back, terminal).await,
                 },
-                Mode::Summary => {
-                    if key.code == KeyCode::Esc {
-                        app.claude_summary = None;
-                        app.mode = Mode::Normal;
-                    }
-                }
-                Mode::Comments => match key.code {
-                    KeyCode::Esc | KeyCode::Char('q') => {
-                        app.mode = Mode::Normal;
-                    }
-                    KeyCode::Char('j') | KeyCode::Down => app.next_comment(),
-                    KeyCode::Char('k') | KeyCode::Up => app.previous_comment(),
-                    KeyCode::Char('r') => {
-                        if let Some(fc) = app.comments.get(app.comments_selected) {
-                            let url = format!(
-                                "https://news.ycombinator.com/reply?id={}&goto=item%3Fid%3D{}%23{}",
-                                fc.comment.id, app.comments_story_id, fc.comment.id
-                            );
-                            match open::that(&url) {
-                                Ok(_) => app
-                                    .set_status_message("Opened reply page in browser".to_string()),
-                                Err(_) => {
-                                    app.set_status_message("Failed to open reply page".to_string())
+                Mode::Normal | Mode::Search => {
+                    let tab = hits
+                        .tabs
+                        .iter()
+                        .find(|(area, _)| area.contains(Position::new(column, row)))
+                        .map(|&(_, section)| section);
+                    if let Some(section) = tab {
+                        if self.mode == Mode::Search {
+                            self.cancel_search();
+                        }
+                        self.switch_section(section, terminal).await;
+                    } else if let Some(i) =
+                        Self::row_in(hits.stories, self.scroll_offset, column, row)
+                    {
+                        if i < self.filtered_stories.len() {
+                            self.select_row(i);
+                            if double {
+                                if self.mode == Mode::Search {
+                                    self.commit_filter();
                                 }
+                                return self.handle_action(Action::OpenStory, terminal).await;
                             }
                         }
                     }
-                    KeyCode::Char('R') => {
-                        if let Err(e) = app.load_comments(&mut terminal).await {
-                            app.set_status_message(format!("Failed to refresh comments: {}", e));
-                        }
-                    }
-                    KeyCode::Char('o') | KeyCode::Enter => {
-                        if let Some(fc) = app.comments.get(app.comments_selected) {
-                            let url =
-                                format!("https://news.ycombinator.com/item?id={}", fc.comment.id);
-                            match open::that(&url) {
-                                Ok(_) => {
-                                    app.set_status_message("Opened comment in browser".to_string())
-                                }
-                                Err(_) => {
-                                    app.set_status_message("Failed to open comment".to_string())
-                                }
-                            }
+                }
+                Mode::Comments | Mode::CommentSearch => {
+                    let scroll = hits.comments_scroll;
+                    let clicked =
+                        Self::row_in(hits.comments, scroll, column, row).and_then(|line| {
+                            hits.comment_lines
+                                .iter()
+                                .position(|&(start, end)| line >= start && line < end)
+                        });
+                    if let Some(i) = clicked {
+                        self.comments_selected = i;
+                        if double {
+                            return self.handle_action(Action::OpenStory, terminal).await;
                         }
                     }
-                    _ => {}
-                },
-                Mode::CommandPalette => match key.code {
-                    KeyCode::Esc => {
-                        app.mode = Mode::Normal;
-                        app.command_palette.search_query.clear();
-                    }
-                    KeyCode::Char(c) => {
-                        app.command_palette.search_query.push(c);
-                        app.command_palette.filter_commands();
-                    }
-                    KeyCode::Backspace => {
-                        app.command_palette.search_query.pop();
-                        app.command_palette.filter_commands();
-                    }
-                    KeyCode::Down => app.command_palette.next_command(),
-                    KeyCode::Up => app.command_palette.previous_command(),
-                    KeyCode::Enter => {
-                        if let Some(cmd) = app.command_palette.get_selected_command() {
-                            match cmd.name.as_str() {
-                                "Refresh" => {
-                                    if let Err(e) = app.refresh_stories(&mut terminal).await {
-                                        app.set_status_message(format!("Refresh failed: {}", e));
-                                    }
-                                }
-                                "Refresh All" => {
-                                    if let Err(e) = app.load_all_sections(&mut terminal).await {
-                                        app.set_status_message(format!(
-                                            "Failed to refresh all sections: {}",
-                                            e
-                                        ));
-                                    }
-                                }
-                                "Switch to Top" | "Switch to Ask" | "Switch to Show"
-                                | "Switch to Jobs" => {
-                                    if let Err(e) = (cmd.action)(&mut app) {
-                                        app.set_status_message(format!(
-                                            "Error switching section: {}",
-                                            e
-                                        ));
-                                    }
-                                    if let Err(e) = app.refresh_stories(&mut terminal).await {
-                                        app.set_status_message(format!(
-                                            "Failed to load stories: {}",
-                                            e
-                                        ));
-                                    }
-                                }
-                                "Search" => {
-                                    let _ = (cmd.action)(&mut app);
-                                }
-                                _ => {
-                                    if let Err(e) = (cmd.action)(&mut app) {
-                                        app.set_status_message(format!(
-                                            "Error executing command: {}",
-                                            e
-                                        ));
-                                    }
-                                }
+                }
+                Mode::RemoteSearch => {
+                    let scroll = self.remote_search.scroll;
+                    if let Some(i) = Self::row_in(hits.search_results, scroll, column, row) {
+                        if i < self.remote_search.results.len() {
+                            self.remote_search.selected = i;
+                            if double {
+                                return self.handle_action(Action::OpenStory, terminal).await;
                             }
                         }
-                        if app.mode != Mode::Search {
-                            app.mode = Mode::Normal;
-                        }
-                        app.command_palette.search_query.clear();
                     }
-                    _ => {}
-                },
-                Mode::Search => match key.code {
-                    KeyCode::Esc => {
-                        app.mode = Mode::Normal;
-                        app.search_query.clear();
-                        app.filtered_stories = (0..app.stories.len()).collect();
-                    }
-                    KeyCode::Char(c) => {
-                        app.search_query.push(c);
-                        app.filter_stories();
-                    }
-                    KeyCode::Backspace => {
-                        app.search_query.pop();
-                        app.filter_stories();
-                    }
-                    KeyCode::Enter => {
-                        if let Some(&story_idx) = app.filtered_stories.get(app.selected_index) {
-                            app.selected_index = story_idx;
-                            app.open_current_story();
-                        }
-                        app.mode = Mode::Normal;
-                        app.search_query.clear();
-                        app.filtered_stories = (0..app.stories.len()).collect();
-                    }
-                    KeyCode::Down => {
-                        if !app.filtered_stories.is_empty() {
-                            app.selected_index =
-                                (app.selected_index + 1) % app.filtered_stories.len();
-                        }
+                }
+                Mode::Summary | Mode::Prompt => {}
+            }
+            true
+        }
+
+        // Keys with no binding in the current mode are typed into the active
+        // input field, if the mode has one.
+        pub fn handle_text_input(&mut self, key: KeyEvent) {
+            let KeyCode::Char(c) = key.code else {
+                return;
+            };
+            if !self.mode.takes_text_input()
+                || key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+            {
+                return;
+            }
+            match self.mode {
+                Mode::CommandPalette => {
+                    self.command_palette.search_query.push(c);
+                    self.command_palette.filter_commands();
+                }
+                Mode::Search => {
+                    self.search_query.push(c);
+                    self.filter_stories();
+                }
+                Mode::Prompt => {
+                    if let Some(prompt) = self.prompt.as_mut() {
+                        prompt.input.push(c);
                     }
-                    KeyCode::Up => {
-                        if !app.filtered_stories.is_empty() {
-                            app.selected_index = app
-                                .selected_index
-                                .checked_sub(1)
-                                .unwrap_or(app.filtered_stories.len() - 1);
-                        }
+                }
+                Mode::RemoteSearch => self.remote_search.input.push(c),
+                Mode::CommentSearch => {
+                    self.comment_query.push(c);
+                    self.update_comment_search();
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+use account::Account;
+use algolia::SearchClient;
+use app::App;
+use bookmarks::Bookmarks;
+use clap::Parser;
+use cli::{Cli, CliError};
+use config::Config;
+use filters::Filters;
+use history::History;
+use hn_web::HnWeb;
+use keymap::{Action, KeyResolution, Keymap};
+use terminal::{Signals, TerminalGuard};
+use theme::Theme;
+
+#[tokio::main]
+async fn main() -> Result<ExitCode, Box<dyn Error + Send + Sync>> {
+    let args = Cli::parse();
+    if let Some(path) = &args.log_file {
+        if let Err(e) = diagnostics::init_log_file(path) {
+            let e = CliError::Config(e);
+            eprintln!("hackertuah: {}", e);
+            return Ok(ExitCode::from(e.exit_code()));
+        }
+    }
+    tracing::info!(version = env!("CARGO_PKG_VERSION"), "starting");
+
+    let (config, config_error) = match Config::load() {
+        Ok(config) => (config, None),
+        Err(e) => (Config::default(), Some(e)),
+    };
+    // Before anything makes a request, so every API goes through the same
+    // proxy and trusts the same certificates. Without them nothing may be
+    // sent at all, so neither the app nor a subcommand starts.
+    if let Err(e) = http::configure(&config.network) {
+        let e = CliError::Config(e);
+        eprintln!("hackertuah: {}", e);
+        return Ok(ExitCode::from(e.exit_code()));
+    }
+
+    if let Some(command) = args.command {
+        if let Some(e) = &config_error {
+            eprintln!("hackertuah: {}", e);
+        }
+        return Ok(cli::run(command).await);
+    }
+
+    let mut guard = TerminalGuard::new(config.mouse)?;
+    let terminal = &mut guard.terminal;
+
+    let mut app = App::new();
+    app.signals = Signals::register()?;
+
+    match config_error {
+        None => {
+            let (keymap, mut errors) = Keymap::from_config(&config);
+            let (theme, theme_errors) = Theme::from_config(&config);
+            errors.extend(theme_errors);
+            app.keymap = keymap;
+            app.theme = theme;
+            if !errors.is_empty() {
+                app.set_status_message(format!("Config: {}", errors.join("; ")));
+            }
+        }
+        Some(e) => {
+            // The default theme still has to respect NO_COLOR.
+            app.theme = Theme::from_config(&Config::default()).0;
+            app.set_status_message(e);
+        }
+    }
+
+    match Bookmarks::load() {
+        Ok(bookmarks) => app.bookmarks = bookmarks,
+        Err(e) => app.set_status_message(e),
+    }
+    match History::load() {
+        Ok(history) => app.history = history,
+        Err(e) => app.set_status_message(e),
+    }
+    match Account::load() {
+        Ok(account) => app.account = account,
+        Err(e) => app.set_status_message(e),
+    }
+    if let Some(base_url) = &config.search.base_url {
+        app.remote_search.client = SearchClient::new(base_url);
+    }
+    if let Some(base_url) = &config.account.base_url {
+        app.hn_web = HnWeb::new(base_url);
+    }
+    let (filters, filter_errors) = Filters::load(&config.mute);
+    app.filters = filters;
+    if !filter_errors.is_empty() {
+        app.set_status_message(format!("Mute rules: {}", filter_errors.join("; ")));
+    }
+
+    app.animation = !args.no_animation;
+    if let Some(section) = args.section {
+        app.current_section = section.section();
+    }
+
+    if let Err(e) = app.load_all_sections(terminal).await {
+        app.report_error("Failed to load sections", e, Action::RefreshAll);
+    }
+
+    if let Some(query) = &args.search {
+        app.apply_filter(query);
+    }
+    if let Some(name) = &args.user {
+        app.search_user(name, terminal).await;
+    }
+    if let Some(id) = args.item {
+        app.open_item(id, terminal).await;
+    }
+
+    loop {
+        if app.signals.terminate_requested() {
+            break;
+        }
+        if app.signals.take_suspend_request() {
+            terminal::suspend(terminal)?;
+        }
+
+        terminal.draw(|f| ui::draw_ui(f, &mut app))?;
+
+        // Poll rather than block so signals are noticed without a key press.
+        if !event::poll(Duration::from_millis(250))? {
+            continue;
+        }
+        let keep_running = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => {
+                match app.keymap.resolve(app.mode, &mut app.pending_keys, key) {
+                    KeyResolution::Action(action) => app.handle_action(action, terminal).await,
+                    KeyResolution::Pending => true,
+                    KeyResolution::Unbound(key) => {
+                        app.handle_text_input(key);
+                        true
                     }
-                    _ => {}
-                },
+                }
             }
+            Event::Mouse(mouse) => app.handle_mouse(mouse, terminal).await,
+            _ => true,
+        };
+        if !keep_running {
+            break;
         }
     }
 
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    // Only print once the terminal is back to normal.
+    drop(guard);
+    for output in &app.stdout_exports {
+        print!("{}", output);
+    }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
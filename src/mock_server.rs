@@ -0,0 +1,192 @@
+//! A local HTTP server for tests: answers every request with whatever a
+//! routing closure returns for it and keeps the requests for inspection.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+// One request as the server read it.
+#[derive(Debug, Clone)]
+pub struct Request {
+    // The request line and headers.
+    pub head: String,
+    pub body: String,
+}
+
+impl Request {
+    pub fn method(&self) -> &str {
+        self.head.split_whitespace().next().unwrap_or_default()
+    }
+
+    // The path and query, or the full URL for a proxied request.
+    pub fn target(&self) -> &str {
+        self.head.split_whitespace().nth(1).unwrap_or_default()
+    }
+
+    // The first value of header `name`, matched case-insensitively as HTTP
+    // clients may send names in any case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.head.lines().skip(1).find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.trim()
+                .eq_ignore_ascii_case(name)
+                .then_some(value.trim())
+        })
+    }
+}
+
+// What the server does with one request.
+#[derive(Debug, Clone)]
+pub enum Response {
+    Reply {
+        status: u16,
+        headers: Vec<(String, String)>,
+        body: String,
+    },
+    // Accepts the request and never answers.
+    Hang,
+    // Closes the connection without answering.
+    Drop,
+}
+
+impl Response {
+    pub fn new(status: u16, body: impl Into<String>) -> Self {
+        Response::Reply {
+            status,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    pub fn ok(body: impl Into<String>) -> Self {
+        Self::new(200, body)
+    }
+
+    pub fn json(body: impl Into<String>) -> Self {
+        Self::ok(body).header("Content-Type", "application/json")
+    }
+
+    pub fn redirect(location: &str) -> Self {
+        Self::new(302, "").header("Location", location)
+    }
+
+    pub fn header(mut self, name: &str, value: impl Into<String>) -> Self {
+        if let Response::Reply { headers, .. } = &mut self {
+            headers.push((name.to_string(), value.into()));
+        }
+        self
+    }
+}
+
+pub struct MockServer {
+    // The server's root, e.g. `http://127.0.0.1:41234`.
+    pub url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl MockServer {
+    pub async fn start<F>(route: F) -> Self
+    where
+        F: Fn(&Request) -> Response + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        let route = Arc::new(route);
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let seen = seen.clone();
+                let route = route.clone();
+                tokio::spawn(async move {
+                    let mut socket = socket;
+                    let Some(request) = read_request(&mut socket).await else {
+                        return;
+                    };
+                    seen.lock().unwrap().push(request.clone());
+                    let response = route(&request);
+                    respond(&mut socket, response).await;
+                });
+            }
+        });
+        MockServer { url, requests }
+    }
+
+    // Every request so far, oldest first.
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub fn hits(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+}
+
+// A route that answers the n-th request with `replies[n]`, repeating the last
+// one.
+pub fn in_turn(replies: Vec<Response>) -> impl Fn(&Request) -> Response + Send + Sync {
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    move |_| {
+        let n = next.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        replies[n.min(replies.len() - 1)].clone()
+    }
+}
+
+// Reads the head and, going by `Content-Length`, the body.
+async fn read_request(socket: &mut TcpStream) -> Option<Request> {
+    let mut data = Vec::new();
+    let mut buf = [0; 4096];
+    loop {
+        match socket.read(&mut buf).await {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => data.extend_from_slice(&buf[..n]),
+        }
+        let text = String::from_utf8_lossy(&data);
+        let Some((head, body)) = text.split_once("\r\n\r\n") else {
+            continue;
+        };
+        let request = Request {
+            head: head.to_string(),
+            body: body.to_string(),
+        };
+        let length = request
+            .header("Content-Length")
+            .and_then(|l| l.parse().ok())
+            .unwrap_or(0);
+        if request.body.len() >= length {
+            return Some(request);
+        }
+    }
+}
+
+async fn respond(socket: &mut TcpStream, response: Response) {
+    let (status, headers, body) = match response {
+        Response::Reply {
+            status,
+            headers,
+            body,
+        } => (status, headers, body),
+        Response::Hang => {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            return;
+        }
+        Response::Drop => return,
+    };
+    let reason = StatusCode::from_u16(status)
+        .ok()
+        .and_then(|s| s.canonical_reason())
+        .unwrap_or("Unknown");
+    let mut head = format!("HTTP/1.1 {} {}\r\n", status, reason);
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    ));
+    let _ = socket.write_all(head.as_bytes()).await;
+    let _ = socket.write_all(body.as_bytes()).await;
+}
@@ -0,0 +1,508 @@
+use crate::keymap::Action;
+use crate::types::Mode;
+
+pub struct PaletteEntry {
+    pub action: Action,
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+// A palette entry that matched the current query, with the positions of the
+// matched characters in `name` so they can be highlighted.
+pub struct PaletteMatch {
+    pub entry: usize,
+    pub positions: Vec<usize>,
+}
+
+pub struct CommandPalette {
+    // Mode the palette was opened from; its entries and actions apply there.
+    pub context: Mode,
+    pub entries: Vec<PaletteEntry>,
+    pub matches: Vec<PaletteMatch>,
+    pub search_query: String,
+    pub selected_index: usize,
+}
+
+impl Default for CommandPalette {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandPalette {
+    pub fn new() -> Self {
+        CommandPalette {
+            context: Mode::Normal,
+            entries: Vec::new(),
+            matches: Vec::new(),
+            search_query: String::new(),
+            selected_index: 0,
+        }
+    }
+
+    pub fn open(&mut self, context: Mode) {
+        self.context = context;
+        self.entries = entries_for(context)
+            .iter()
+            .map(|&(action, name, description)| PaletteEntry {
+                action,
+                name,
+                description,
+            })
+            .collect();
+        self.search_query.clear();
+        self.filter_commands();
+    }
+
+    pub fn filter_commands(&mut self) {
+        let query = self.search_query.trim().to_lowercase();
+        let mut scored: Vec<(i32, PaletteMatch)> = Vec::new();
+        for (i, entry) in self.entries.iter().enumerate() {
+            let result = if query.is_empty() {
+                Some((0, Vec::new()))
+            } else if let Some(found) = fuzzy_match(&query, entry.name) {
+                Some(found)
+            } else if entry.description.to_lowercase().contains(&query) {
+                // Description matches rank below any match on the name.
+                Some((i32::MIN / 2, Vec::new()))
+            } else {
+                None
+            };
+            if let Some((score, positions)) = result {
+                scored.push((
+                    score,
+                    PaletteMatch {
+                        entry: i,
+                        positions,
+                    },
+                ));
+            }
+        }
+        // Stable sort keeps the declaration order for equal scores.
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        self.matches = scored.into_iter().map(|(_, m)| m).collect();
+        self.selected_index = 0;
+    }
+
+    pub fn next_command(&mut self) {
+        if !self.matches.is_empty() {
+            self.selected_index = (self.selected_index + 1) % self.matches.len();
+        }
+    }
+
+    pub fn previous_command(&mut self) {
+        if !self.matches.is_empty() {
+            self.selected_index = self
+                .selected_index
+                .checked_sub(1)
+                .unwrap_or(self.matches.len() - 1);
+        }
+    }
+
+    pub fn get_selected_command(&self) -> Option<&PaletteEntry> {
+        self.matches
+            .get(self.selected_index)
+            .map(|m| &self.entries[m.entry])
+    }
+}
+
+// Scores `candidate` against `query` as a case-insensitive subsequence match.
+// Consecutive characters and matches at word starts score higher, gaps score
+// lower. Returns the best score and the char positions it matched.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<(i32, Vec<usize>)> {
+    const MATCH: i32 = 16;
+    const WORD_START: i32 = 24;
+    const CONSECUTIVE: i32 = 32;
+    const GAP: i32 = 2;
+
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    let chars: Vec<char> = candidate.chars().collect();
+    let lower: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    if query.is_empty() || query.len() > chars.len() {
+        return None;
+    }
+
+    let bonus = |j: usize| {
+        let word_start = j == 0
+            || !chars[j - 1].is_alphanumeric()
+            || (chars[j - 1].is_lowercase() && chars[j].is_uppercase());
+        MATCH + if word_start { WORD_START } else { 0 }
+    };
+
+    // best[i][j]: best score with query[i] matched at candidate position j.
+    let n = chars.len();
+    let mut best = vec![vec![None::<i32>; n]; query.len()];
+    let mut from = vec![vec![0usize; n]; query.len()];
+    for j in 0..n {
+        if lower[j] == query[0] {
+            best[0][j] = Some(bonus(j) - j as i32);
+        }
+    }
+    for i in 1..query.len() {
+        for j in i..n {
+            if lower[j] != query[i] {
+                continue;
+            }
+            for k in (i - 1)..j {
+                let Some(prev) = best[i - 1][k] else {
+                    continue;
+                };
+                let step = if k + 1 == j {
+                    CONSECUTIVE
+                } else {
+                    -GAP * (j - k - 1) as i32
+                };
+                let score = prev + bonus(j) + step;
+                if best[i][j].is_none_or(|s| score > s) {
+                    best[i][j] = Some(score);
+                    from[i][j] = k;
+                }
+            }
+        }
+    }
+
+    // Unmatched trailing characters cost a little, so shorter names win ties.
+    let last = query.len() - 1;
+    let (mut j, score) = (0..n)
+        .filter_map(|j| best[last][j].map(|s| (j, s - (n - 1 - j) as i32)))
+        .max_by_key(|&(_, s)| s)?;
+    let mut positions = vec![0; query.len()];
+    for i in (0..query.len()).rev() {
+        positions[i] = j;
+        if i > 0 {
+            j = from[i][j];
+        }
+    }
+    Some((score, positions))
+}
+
+type Entries = &'static [(Action, &'static str, &'static str)];
+
+fn entries_for(mode: Mode) -> Entries {
+    match mode {
+        Mode::Normal | Mode::CommandPalette => &[
+            (
+                Action::OpenStory,
+                "Open in Browser",
+                "Open the selected story in your default browser",
+            ),
+            (
+                Action::OpenComments,
+                "View Comments",
+                "Read the comments for the selected story",
+            ),
+            (
+                Action::OpenCommentsInBrowser,
+                "Open Comments in Browser",
+                "Open the HN discussion page in your browser",
+            ),
+            (
+                Action::Summarize,
+                "Summarize",
+                "Get an AI summary of the selected story",
+            ),
+            (
+                Action::OpenMenu,
+                "Options",
+                "Open the options menu for the selected story",
+            ),
+            (
+                Action::Bookmark,
+                "Bookmark Story",
+                "Save or unsave the selected story with tags and a note",
+            ),
+            (
+                Action::Upvote,
+                "Upvote Story",
+                "Upvote the selected story on HN, or take the vote back",
+            ),
+            (
+                Action::Favorite,
+                "Favorite Story",
+                "Add the selected story to your HN favorites, or remove it",
+            ),
+            (
+                Action::HideStory,
+                "Hide Story on HN",
+                "Hide the selected story on HN, or unhide it",
+            ),
+            (
+                Action::LogIn,
+                "Log In to HN",
+                "Log in to vote, favorite and hide as your HN account",
+            ),
+            (
+                Action::LogOut,
+                "Log Out of HN",
+                "Forget the saved HN session",
+            ),
+            (
+                Action::MuteUser,
+                "Mute User",
+                "Hide stories and comments by the selected story's author",
+            ),
+            (
+                Action::MuteDomain,
+                "Mute Domain",
+                "Hide stories linking to the selected story's domain",
+            ),
+            (
+                Action::MutePattern,
+                "Hide Stories Matching",
+                "Hide stories whose title matches a regular expression",
+            ),
+            (
+                Action::ToggleHidden,
+                "Toggle Muted Items",
+                "Temporarily show or hide muted stories",
+            ),
+            (
+                Action::CycleSort,
+                "Cycle Sort Order",
+                "Sort by rank, score, comments, age, points/hour or comments/point",
+            ),
+            (Action::Search, "Search", "Filter stories by text"),
+            (
+                Action::Back,
+                "Clear Filter",
+                "Show the whole list again after keeping a filter",
+            ),
+            (
+                Action::RemoteSearch,
+                "Search Hacker News",
+                "Search all of HN's history through the Algolia API",
+            ),
+            (
+                Action::GoTo,
+                "Go to Item",
+                "Open a story, comment, user or site by id or HN link",
+            ),
+            (
+                Action::ToggleDebug,
+                "Debug Overlay",
+                "Show recent requests, running tasks and error counts",
+            ),
+            (
+                Action::SectionTop,
+                "Switch to Top",
+                "Switch to Top stories section",
+            ),
+            (
+                Action::SectionAsk,
+                "Switch to Ask",
+                "Switch to Ask HN section",
+            ),
+            (
+                Action::SectionShow,
+                "Switch to Show",
+                "Switch to Show HN section",
+            ),
+            (
+                Action::SectionJobs,
+                "Switch to Jobs",
+                "Switch to Jobs section",
+            ),
+            (
+                Action::SectionSaved,
+                "Switch to Saved",
+                "Browse your bookmarked stories and comments",
+            ),
+            (
+                Action::NextSection,
+                "Next Section",
+                "Switch to the next section",
+            ),
+            (
+                Action::PreviousSection,
+                "Previous Section",
+                "Switch to the previous section",
+            ),
+            (
+                Action::MoveTop,
+                "Go to First Story",
+                "Select the first story in the list",
+            ),
+            (
+                Action::MoveBottom,
+                "Go to Last Story",
+                "Select the last story in the list",
+            ),
+            (Action::Refresh, "Refresh", "Refresh the current section"),
+            (Action::RefreshAll, "Refresh All", "Refresh all sections"),
+            (Action::Quit, "Quit", "Exit the application"),
+        ],
+        Mode::Comments => &[
+            (
+                Action::OpenStory,
+                "Open Comment in Browser",
+                "Open the selected comment on news.ycombinator.com",
+            ),
+            (
+                Action::NextNewComment,
+                "Next New Comment",
+                "Jump to the next comment added since your last visit",
+            ),
+            (
+                Action::PreviousNewComment,
+                "Previous New Comment",
+                "Jump to the previous comment added since your last visit",
+            ),
+            (
+                Action::Search,
+                "Search Thread",
+                "Find comments by text or author",
+            ),
+            (
+                Action::NextMatch,
+                "Next Match",
+                "Jump to the next comment matching the search",
+            ),
+            (
+                Action::PreviousMatch,
+                "Previous Match",
+                "Jump to the previous comment matching the search",
+            ),
+            (
+                Action::ToggleCollapse,
+                "Collapse / Expand Replies",
+                "Fold or unfold the replies under the selected comment",
+            ),
+            (
+                Action::Reply,
+                "Reply",
+                "Open the HN reply page for the selected comment",
+            ),
+            (
+                Action::Export,
+                "Export Thread",
+                "Save the story and loaded comments as Markdown, JSON or HTML",
+            ),
+            (
+                Action::GoTo,
+                "Go to Item",
+                "Open a story, comment, user or site by id or HN link",
+            ),
+            (
+                Action::ToggleDebug,
+                "Debug Overlay",
+                "Show recent requests, running tasks and error counts",
+            ),
+            (
+                Action::Bookmark,
+                "Bookmark Comment",
+                "Save or unsave the selected comment with tags and a note",
+            ),
+            (
+                Action::Upvote,
+                "Upvote Comment",
+                "Upvote the selected comment on HN, or take the vote back",
+            ),
+            (
+                Action::Favorite,
+                "Favorite Comment",
+                "Add the selected comment to your HN favorites, or remove it",
+            ),
+            (
+                Action::LogIn,
+                "Log In to HN",
+                "Log in to vote, favorite and hide as your HN account",
+            ),
+            (
+                Action::LogOut,
+                "Log Out of HN",
+                "Forget the saved HN session",
+            ),
+            (
+                Action::MuteUser,
+                "Mute User",
+                "Hide comments by the selected comment's author",
+            ),
+            (
+                Action::ToggleHidden,
+                "Toggle Muted Comments",
+                "Temporarily show or hide comments from muted users",
+            ),
+            (
+                Action::MoveTop,
+                "Go to First Comment",
+                "Select the first comment in the thread",
+            ),
+            (
+                Action::MoveBottom,
+                "Go to Last Comment",
+                "Select the last comment in the thread",
+            ),
+            (
+                Action::Refresh,
+                "Refresh Comments",
+                "Reload the comments for this story",
+            ),
+            (Action::Back, "Back to Stories", "Leave the comments view"),
+            (Action::Quit, "Quit", "Exit the application"),
+        ],
+        Mode::Search => &[
+            (
+                Action::CommitFilter,
+                "Keep Filter",
+                "Close the filter bar and keep browsing the filtered list",
+            ),
+            (
+                Action::Back,
+                "Cancel Search",
+                "Clear the filter and return to the story list",
+            ),
+            (Action::Quit, "Quit", "Exit the application"),
+        ],
+        Mode::Menu => &[
+            (
+                Action::Summarize,
+                "Summarize",
+                "Get an AI summary of the selected story",
+            ),
+            (
+                Action::OpenStory,
+                "Open in Browser",
+                "Open the selected story in your default browser",
+            ),
+            (Action::Back, "Close Menu", "Close the options menu"),
+            (Action::Quit, "Quit", "Exit the application"),
+        ],
+        Mode::RemoteSearch => &[
+            (
+                Action::Select,
+                "Search / Open Result",
+                "Run the search, or open the selected result's thread",
+            ),
+            (
+                Action::OpenStory,
+                "Open Result",
+                "Open the selected result in the comments view",
+            ),
+            (
+                Action::ToggleSearchKind,
+                "Toggle Stories / Comments",
+                "Switch between searching stories and comments",
+            ),
+            (
+                Action::ToggleSearchSort,
+                "Toggle Sort",
+                "Sort results by relevance or by date",
+            ),
+            (Action::Back, "Close Search", "Return to the story list"),
+            (Action::Quit, "Quit", "Exit the application"),
+        ],
+        // No key opens the palette while typing into these.
+        Mode::CommentSearch | Mode::Prompt => &[],
+        Mode::Summary => &[
+            (Action::Back, "Close Summary", "Close the summary popup"),
+            (Action::Quit, "Quit", "Exit the application"),
+        ],
+    }
+}
@@ -0,0 +1,200 @@
+use crate::types::Story;
+
+// A parsed filter-bar query. Every clause has to match (AND); a leading `-`
+// negates a clause.
+//
+//   rust "type system" -crypto by:dang domain:github.com score>200 comments>=50
+//
+// Bare words and quoted phrases match the title, author, domain and self-post
+// text. Fields: `by:` (or `author:`), `domain:` (or `site:`), `title:`,
+// `text:`, and the numeric `score` (or `points`) and `comments`, compared with
+// `>`, `>=`, `<`, `<=` or `=`.
+#[derive(Debug, Default)]
+pub struct StoryQuery {
+    clauses: Vec<Clause>,
+}
+
+#[derive(Debug)]
+struct Clause {
+    negated: bool,
+    term: Term,
+}
+
+#[derive(Debug)]
+enum Term {
+    Text(String),
+    Author(String),
+    Domain(String),
+    Title(String),
+    SelfText(String),
+    Score(Comparison, i64),
+    Comments(Comparison, i64),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Comparison {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    Equal,
+}
+
+impl Comparison {
+    fn test(&self, value: i64, target: i64) -> bool {
+        match self {
+            Comparison::Greater => value > target,
+            Comparison::GreaterOrEqual => value >= target,
+            Comparison::Less => value < target,
+            Comparison::LessOrEqual => value <= target,
+            Comparison::Equal => value == target,
+        }
+    }
+}
+
+// Splits on whitespace, keeping quoted runs (`"exact phrase"`,
+// `title:"two words"`) together.
+fn tokenize(input: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in input.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                current.push(c);
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if in_quotes {
+        return Err("missing closing quote".to_string());
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+fn unquote(value: &str) -> String {
+    value.replace('"', "").to_lowercase()
+}
+
+fn parse_number(field: &str, rest: &str) -> Result<(Comparison, i64), String> {
+    let (comparison, value) = if let Some(v) = rest.strip_prefix(">=") {
+        (Comparison::GreaterOrEqual, v)
+    } else if let Some(v) = rest.strip_prefix("<=") {
+        (Comparison::LessOrEqual, v)
+    } else if let Some(v) = rest.strip_prefix('>') {
+        (Comparison::Greater, v)
+    } else if let Some(v) = rest.strip_prefix('<') {
+        (Comparison::Less, v)
+    } else if let Some(v) = rest.strip_prefix('=').or_else(|| rest.strip_prefix(':')) {
+        (Comparison::Equal, v)
+    } else {
+        return Err(format!("{} needs a comparison, e.g. {}>100", field, field));
+    };
+    let value = value
+        .parse()
+        .map_err(|_| format!("{} needs a number, got '{}'", field, value))?;
+    Ok((comparison, value))
+}
+
+fn parse_term(token: &str) -> Result<Term, String> {
+    for (field, numeric) in [
+        ("score", true),
+        ("points", true),
+        ("comments", true),
+        ("by:", false),
+        ("author:", false),
+        ("domain:", false),
+        ("site:", false),
+        ("title:", false),
+        ("text:", false),
+    ] {
+        let Some(rest) = token.strip_prefix(field) else {
+            continue;
+        };
+        if numeric {
+            // `scores` or `comments-are-bad` are just words.
+            if !rest.starts_with(['>', '<', '=', ':']) {
+                continue;
+            }
+            let (comparison, value) = parse_number(field, rest)?;
+            return Ok(match field {
+                "comments" => Term::Comments(comparison, value),
+                _ => Term::Score(comparison, value),
+            });
+        }
+        let value = unquote(rest);
+        if value.is_empty() {
+            return Err(format!("{} needs a value", field));
+        }
+        return Ok(match field {
+            "by:" | "author:" => Term::Author(value),
+            "domain:" | "site:" => {
+                Term::Domain(value.strip_prefix("www.").unwrap_or(&value).to_string())
+            }
+            "title:" => Term::Title(value),
+            _ => Term::SelfText(value),
+        });
+    }
+    Ok(Term::Text(unquote(token)))
+}
+
+impl StoryQuery {
+    pub fn parse(input: &str) -> Result<StoryQuery, String> {
+        let mut clauses = Vec::new();
+        for token in tokenize(input)? {
+            let (negated, token) = match token.strip_prefix('-') {
+                Some(rest) if !rest.is_empty() => (true, rest),
+                _ => (false, token.as_str()),
+            };
+            let term = parse_term(token)?;
+            // A lone `""` matches everything; drop it.
+            if matches!(&term, Term::Text(t) if t.is_empty()) {
+                continue;
+            }
+            clauses.push(Clause { negated, term });
+        }
+        Ok(StoryQuery { clauses })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    pub fn matches(&self, story: &Story) -> bool {
+        let title = story.title.to_lowercase();
+        let author = story.by.to_lowercase();
+        let domain = story.domain().unwrap_or_default();
+        let text = story.text.as_deref().unwrap_or_default().to_lowercase();
+        self.clauses.iter().all(|clause| {
+            let hit = match &clause.term {
+                Term::Text(t) => {
+                    title.contains(t)
+                        || author.contains(t)
+                        || domain.contains(t)
+                        || text.contains(t)
+                }
+                Term::Author(a) => author == *a,
+                Term::Domain(d) => {
+                    domain == *d
+                        || domain
+                            .strip_suffix(d.as_str())
+                            .is_some_and(|s| s.ends_with('.'))
+                }
+                Term::Title(t) => title.contains(t),
+                Term::SelfText(t) => text.contains(t),
+                Term::Score(c, n) => c.test(story.score as i64, *n),
+                Term::Comments(c, n) => c.test(story.descendants as i64, *n),
+            };
+            hit != clause.negated
+        })
+    }
+}
@@ -0,0 +1,129 @@
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{backend::CrosstermBackend, Terminal};
+
+pub type Tui = Terminal<CrosstermBackend<io::Stdout>>;
+
+// Whether the terminal is currently in raw mode on the alternate screen, so
+// that restoring from several places (drop, panic hook, suspend) is harmless.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static MOUSE: AtomicBool = AtomicBool::new(false);
+
+fn enter() -> io::Result<()> {
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    if MOUSE.load(Ordering::SeqCst) {
+        execute!(io::stdout(), EnableMouseCapture)?;
+    }
+    ACTIVE.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+// Leaves raw mode and the alternate screen and shows the cursor again. Safe to
+// call more than once.
+pub fn restore() -> io::Result<()> {
+    if !ACTIVE.swap(false, Ordering::SeqCst) {
+        return Ok(());
+    }
+    disable_raw_mode()?;
+    execute!(
+        io::stdout(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        crossterm::cursor::Show
+    )?;
+    Ok(())
+}
+
+// Owns the TUI terminal and puts the user's terminal back the way it was when
+// dropped, whether `main` returns normally, bails out early with `?` or unwinds
+// from a panic.
+pub struct TerminalGuard {
+    pub terminal: Tui,
+}
+
+impl TerminalGuard {
+    pub fn new(mouse: bool) -> io::Result<TerminalGuard> {
+        MOUSE.store(mouse, Ordering::SeqCst);
+        install_panic_hook();
+        enter()?;
+        let terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+        Ok(TerminalGuard { terminal })
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = restore();
+    }
+}
+
+// Restores the terminal before the default hook prints the panic message, so
+// the message ends up on the normal screen instead of being wiped with the
+// alternate one.
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = restore();
+        default_hook(info);
+    }));
+}
+
+// Stops the process like a shell's Ctrl+Z would and picks the TUI back up when
+// the job is resumed with `fg`.
+#[cfg(unix)]
+pub fn suspend(terminal: &mut Tui) -> io::Result<()> {
+    restore()?;
+    signal_hook::low_level::emulate_default_handler(signal_hook::consts::SIGTSTP)?;
+    // Execution continues here after SIGCONT.
+    enter()?;
+    terminal.clear()?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn suspend(_terminal: &mut Tui) -> io::Result<()> {
+    Ok(())
+}
+
+// Flags raised by signals from outside the app, checked by the event loops.
+#[derive(Clone, Default)]
+pub struct Signals {
+    terminate: Arc<AtomicBool>,
+    suspend: Arc<AtomicBool>,
+}
+
+impl Signals {
+    // Catches SIGTERM, SIGHUP and SIGQUIT for a clean shutdown, and SIGTSTP
+    // (e.g. `kill -TSTP`) so the terminal is restored before the app stops.
+    #[cfg(unix)]
+    pub fn register() -> io::Result<Signals> {
+        use signal_hook::consts::{SIGHUP, SIGQUIT, SIGTERM, SIGTSTP};
+        let signals = Signals::default();
+        for signal in [SIGTERM, SIGHUP, SIGQUIT] {
+            signal_hook::flag::register(signal, Arc::clone(&signals.terminate))?;
+        }
+        signal_hook::flag::register(SIGTSTP, Arc::clone(&signals.suspend))?;
+        Ok(signals)
+    }
+
+    #[cfg(not(unix))]
+    pub fn register() -> io::Result<Signals> {
+        Ok(Signals::default())
+    }
+
+    pub fn terminate_requested(&self) -> bool {
+        self.terminate.load(Ordering::SeqCst)
+    }
+
+    pub fn take_suspend_request(&self) -> bool {
+        self.suspend.swap(false, Ordering::SeqCst)
+    }
+}
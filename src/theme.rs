@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+
+use crate::config::Config;
+
+// Named style roles used by the UI. Every widget picks its style from one of
+// these instead of hard-coding colors, so a theme fully controls the look.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub name: String,
+    pub text: Style,
+    pub title: Style,
+    pub tab: Style,
+    pub tab_active: Style,
+    pub selected: Style,
+    pub muted: Style,
+    // Stories whose article or thread has been opened before.
+    pub visited: Style,
+    pub author: Style,
+    pub comment_text: Style,
+    pub tree_gutter: Style,
+    pub comment_selected: Style,
+    pub help_key: Style,
+    pub help_text: Style,
+    pub popup_selected: Style,
+    pub highlight: Style,
+    pub rain: Style,
+}
+
+pub const BUILTIN_THEMES: [&str; 4] = ["matrix", "light", "solarized", "monochrome"];
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::matrix()
+    }
+}
+
+impl Theme {
+    // The classic green-on-black look.
+    pub fn matrix() -> Theme {
+        let green = Style::default().fg(Color::Green);
+        let gray = Style::default().fg(Color::DarkGray);
+        Theme {
+            name: "matrix".to_string(),
+            text: green,
+            title: green,
+            tab: green,
+            tab_active: green.add_modifier(Modifier::REVERSED),
+            selected: green.add_modifier(Modifier::REVERSED),
+            muted: gray,
+            visited: Style::default().fg(Color::Rgb(0, 135, 0)),
+            author: Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            comment_text: green,
+            tree_gutter: gray,
+            comment_selected: Style::default().bg(Color::Rgb(0, 40, 0)),
+            help_key: Style::default()
+                .fg(Color::Black)
+                .bg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+            help_text: gray,
+            popup_selected: Style::default().fg(Color::Black).bg(Color::Green),
+            highlight: Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            rain: green,
+        }
+    }
+
+    // Dark text for terminals with a light background.
+    pub fn light() -> Theme {
+        let text = Style::default().fg(Color::Black);
+        let gray = Style::default().fg(Color::DarkGray);
+        let inverse = Style::default().fg(Color::White).bg(Color::Blue);
+        Theme {
+            name: "light".to_string(),
+            text,
+            title: Style::default()
+                .fg(Color::Blue)
+                .add_modifier(Modifier::BOLD),
+            tab: Style::default().fg(Color::Blue),
+            tab_active: inverse,
+            selected: inverse,
+            muted: gray,
+            visited: gray,
+            author: Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+            comment_text: text,
+            tree_gutter: gray,
+            comment_selected: Style::default().bg(Color::Rgb(225, 235, 250)),
+            help_key: inverse.add_modifier(Modifier::BOLD),
+            help_text: gray,
+            popup_selected: inverse,
+            highlight: Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            rain: Style::default().fg(Color::Blue),
+        }
+    }
+
+    // Ethan Schoonover's Solarized (dark) palette.
+    pub fn solarized() -> Theme {
+        let base03 = Color::Rgb(0x00, 0x2b, 0x36);
+        let base02 = Color::Rgb(0x07, 0x36, 0x42);
+        let base01 = Color::Rgb(0x58, 0x6e, 0x75);
+        let base0 = Color::Rgb(0x83, 0x94, 0x96);
+        let base1 = Color::Rgb(0x93, 0xa1, 0xa1);
+        let yellow = Color::Rgb(0xb5, 0x89, 0x00);
+        let blue = Color::Rgb(0x26, 0x8b, 0xd2);
+        let cyan = Color::Rgb(0x2a, 0xa1, 0x98);
+        Theme {
+            name: "solarized".to_string(),
+            text: Style::default().fg(base0),
+            title: Style::default().fg(blue).add_modifier(Modifier::BOLD),
+            tab: Style::default().fg(base1),
+            tab_active: Style::default().fg(base03).bg(blue),
+            selected: Style::default().fg(base1).bg(base02),
+            muted: Style::default().fg(base01),
+            visited: Style::default().fg(base01),
+            author: Style::default().fg(yellow).add_modifier(Modifier::BOLD),
+            comment_text: Style::default().fg(base1),
+            tree_gutter: Style::default().fg(base01),
+            comment_selected: Style::default().bg(base02),
+            help_key: Style::default()
+                .fg(base03)
+                .bg(cyan)
+                .add_modifier(Modifier::BOLD),
+            help_text: Style::default().fg(base01),
+            popup_selected: Style::default().fg(base03).bg(blue),
+            highlight: Style::default().fg(yellow).add_modifier(Modifier::BOLD),
+            rain: Style::default().fg(cyan),
+        }
+    }
+
+    // No colors at all, only text attributes. Used whenever `NO_COLOR` is set.
+    pub fn monochrome() -> Theme {
+        let plain = Style::default();
+        let reversed = plain.add_modifier(Modifier::REVERSED);
+        Theme {
+            name: "monochrome".to_string(),
+            text: plain,
+            title: plain.add_modifier(Modifier::BOLD),
+            tab: plain,
+            tab_active: reversed,
+            selected: reversed,
+            muted: plain.add_modifier(Modifier::DIM),
+            visited: plain.add_modifier(Modifier::DIM),
+            author: plain.add_modifier(Modifier::BOLD),
+            comment_text: plain,
+            tree_gutter: plain.add_modifier(Modifier::DIM),
+            comment_selected: plain.add_modifier(Modifier::BOLD),
+            help_key: reversed.add_modifier(Modifier::BOLD),
+            help_text: plain.add_modifier(Modifier::DIM),
+            popup_selected: reversed,
+            highlight: plain.add_modifier(Modifier::UNDERLINED | Modifier::BOLD),
+            rain: plain.add_modifier(Modifier::DIM),
+        }
+    }
+
+    pub fn builtin(name: &str) -> Option<Theme> {
+        match name {
+            "matrix" => Some(Self::matrix()),
+            "light" => Some(Self::light()),
+            "solarized" => Some(Self::solarized()),
+            "monochrome" => Some(Self::monochrome()),
+            _ => None,
+        }
+    }
+
+    fn role_mut(&mut self, role: &str) -> Option<&mut Style> {
+        let style = match role {
+            "text" => &mut self.text,
+            "title" => &mut self.title,
+            "tab" => &mut self.tab,
+            "tab_active" => &mut self.tab_active,
+            "selected" => &mut self.selected,
+            "muted" => &mut self.muted,
+            "visited" => &mut self.visited,
+            "author" => &mut self.author,
+            "comment_text" => &mut self.comment_text,
+            "tree_gutter" => &mut self.tree_gutter,
+            "comment_selected" => &mut self.comment_selected,
+            "help_key" => &mut self.help_key,
+            "help_text" => &mut self.help_text,
+            "popup_selected" => &mut self.popup_selected,
+            "highlight" => &mut self.highlight,
+            "rain" => &mut self.rain,
+            _ => return None,
+        };
+        Some(style)
+    }
+
+    // Resolves the theme named by `theme = "..."`, looking at user themes from
+    // `[themes.<name>]` before the built-ins. `NO_COLOR` always wins.
+    pub fn from_config(config: &Config) -> (Theme, Vec<String>) {
+        if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+            return (Self::monochrome(), Vec::new());
+        }
+        let mut errors = Vec::new();
+        let name = config.theme.as_deref().unwrap_or("matrix");
+        let theme = match resolve(name, &config.themes, &mut errors, 0) {
+            Some(theme) => theme,
+            None => {
+                errors.push(format!(
+                    "unknown theme '{}' (built-in themes: {})",
+                    name,
+                    BUILTIN_THEMES.join(", ")
+                ));
+                Self::default()
+            }
+        };
+        (theme, errors)
+    }
+}
+
+// A user theme: an optional built-in (or other user theme) to start from, plus
+// style overrides keyed by role name.
+#[derive(Debug, Default, Deserialize)]
+pub struct ThemeSpec {
+    pub base: Option<String>,
+    #[serde(flatten)]
+    pub roles: HashMap<String, StyleSpec>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct StyleSpec {
+    pub fg: Option<String>,
+    pub bg: Option<String>,
+    pub modifiers: Vec<String>,
+}
+
+impl StyleSpec {
+    fn to_style(&self) -> Result<Style, String> {
+        let mut style = Style::default();
+        if let Some(fg) = &self.fg {
+            style = style.fg(parse_color(fg)?);
+        }
+        if let Some(bg) = &self.bg {
+            style = style.bg(parse_color(bg)?);
+        }
+        for name in &self.modifiers {
+            style = style.add_modifier(parse_modifier(name)?);
+        }
+        Ok(style)
+    }
+}
+
+fn resolve(
+    name: &str,
+    themes: &HashMap<String, ThemeSpec>,
+    errors: &mut Vec<String>,
+    depth: usize,
+) -> Option<Theme> {
+    let Some(spec) = themes.get(name) else {
+        return Theme::builtin(name);
+    };
+    if depth > 8 {
+        errors.push(format!("theme '{}' has a circular base", name));
+        return None;
+    }
+    let base = spec.base.as_deref().unwrap_or("matrix");
+    // A user theme may shadow a built-in of the same name and extend it.
+    let base_theme = if base == name {
+        Theme::builtin(base)
+    } else {
+        resolve(base, themes, errors, depth + 1)
+    };
+    let mut theme = match base_theme {
+        Some(theme) => theme,
+        None => {
+            errors.push(format!("theme '{}': unknown base '{}'", name, base));
+            Theme::default()
+        }
+    };
+    theme.name = name.to_string();
+    for (role, spec) in &spec.roles {
+        let style = match spec.to_style() {
+            Ok(style) => style,
+            Err(e) => {
+                errors.push(format!("theme '{}' {}: {}", name, role, e));
+                continue;
+            }
+        };
+        match theme.role_mut(role) {
+            Some(slot) => *slot = style,
+            None => errors.push(format!("theme '{}': unknown role '{}'", name, role)),
+        }
+    }
+    Some(theme)
+}
+
+// Accepts color names (`green`, `darkgray`), 256-color indexes (`208`) and
+// hex triplets (`#268bd2`).
+fn parse_color(s: &str) -> Result<Color, String> {
+    Color::from_str(s).map_err(|_| format!("invalid color '{}'", s))
+}
+
+fn parse_modifier(s: &str) -> Result<Modifier, String> {
+    let modifier = match s.to_lowercase().as_str() {
+        "bold" => Modifier::BOLD,
+        "dim" => Modifier::DIM,
+        "italic" => Modifier::ITALIC,
+        "underlined" | "underline" => Modifier::UNDERLINED,
+        "reversed" | "reverse" => Modifier::REVERSED,
+        "crossed_out" | "strikethrough" => Modifier::CROSSED_OUT,
+        "slow_blink" | "blink" => Modifier::SLOW_BLINK,
+        _ => return Err(format!("invalid modifier '{}'", s)),
+    };
+    Ok(modifier)
+}
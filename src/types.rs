@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Story {
     pub id: u32,
     pub title: String,
@@ -8,15 +8,42 @@ pub struct Story {
     pub text: Option<String>,
     pub by: String,
     pub score: i32,
+    // Submission time, Unix seconds.
+    #[serde(default)]
+    pub time: u64,
     #[serde(default)]
     pub descendants: u32,
     #[serde(default)]
     pub kids: Vec<u32>,
+    // A poll's options, as `pollopt` item ids.
+    #[serde(default)]
+    pub parts: Vec<u32>,
+}
+
+impl Story {
+    pub fn is_poll(&self) -> bool {
+        !self.parts.is_empty()
+    }
+
+    // Host of the story's link without a leading `www.`, lowercased.
+    pub fn domain(&self) -> Option<String> {
+        let url = self.url.as_deref()?;
+        let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+        let host = rest.split(['/', '?', '#']).next()?;
+        // Drop userinfo and port.
+        let host = host.rsplit('@').next()?.split(':').next()?;
+        let host = host.to_lowercase();
+        let host = host.strip_prefix("www.").unwrap_or(&host);
+        (!host.is_empty()).then(|| host.to_string())
+    }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Comment {
     pub id: u32,
+    // The comment or story this replies to.
+    #[serde(default)]
+    pub parent: Option<u32>,
     #[serde(default)]
     pub by: String,
     #[serde(default)]
@@ -31,40 +58,158 @@ pub struct Comment {
     pub dead: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct User {
+    pub id: String,
+    // Account creation time, Unix seconds.
+    #[serde(default)]
+    pub created: u64,
+    #[serde(default)]
+    pub karma: i32,
+    // As returned by the HN API, i.e. HTML.
+    #[serde(default)]
+    pub about: Option<String>,
+    // Ids of the user's stories, comments and polls, newest first.
+    #[serde(default)]
+    pub submitted: Vec<u32>,
+}
+
+// One option of a poll, with its votes.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PollOption {
+    pub id: u32,
+    // As returned by the HN API, i.e. HTML.
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub score: i32,
+}
+
 pub struct FlatComment {
     pub comment: Comment,
     pub depth: usize,
 }
 
+// Order of the story list. `Rank` keeps the feed's own ordering; the others
+// put the highest value first (newest first for `Age`).
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum SortOrder {
+    Rank,
+    Score,
+    Comments,
+    Age,
+    PointsPerHour,
+    CommentRatio,
+}
+
+impl SortOrder {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortOrder::Rank => "rank",
+            SortOrder::Score => "score",
+            SortOrder::Comments => "comments",
+            SortOrder::Age => "newest",
+            SortOrder::PointsPerHour => "points/hour",
+            SortOrder::CommentRatio => "comments/point",
+        }
+    }
+
+    pub fn next(&self) -> SortOrder {
+        match self {
+            SortOrder::Rank => SortOrder::Score,
+            SortOrder::Score => SortOrder::Comments,
+            SortOrder::Comments => SortOrder::Age,
+            SortOrder::Age => SortOrder::PointsPerHour,
+            SortOrder::PointsPerHour => SortOrder::CommentRatio,
+            SortOrder::CommentRatio => SortOrder::Rank,
+        }
+    }
+
+    // Sorts `stories` (given in feed order) in place. The sort is stable, so
+    // ties keep their rank.
+    pub fn sort(&self, stories: &mut [Story], now: u64) {
+        let hours = |s: &Story| (now.saturating_sub(s.time) as f64 / 3600.0).max(0.1);
+        let key = |s: &Story| -> f64 {
+            match self {
+                SortOrder::Rank => 0.0,
+                SortOrder::Score => s.score as f64,
+                SortOrder::Comments => s.descendants as f64,
+                SortOrder::Age => s.time as f64,
+                SortOrder::PointsPerHour => s.score as f64 / hours(s),
+                SortOrder::CommentRatio => s.descendants as f64 / s.score.max(1) as f64,
+            }
+        };
+        if *self != SortOrder::Rank {
+            stories.sort_by(|a, b| key(b).total_cmp(&key(a)));
+        }
+    }
+}
+
 #[derive(PartialEq, Eq, Hash, Clone, Copy)]
 pub enum Section {
     Top,
     Ask,
     Show,
     Jobs,
+    // Bookmarked stories and comments, stored locally.
+    Saved,
 }
 
 impl Section {
+    pub const ALL: [Section; 5] = [
+        Section::Top,
+        Section::Ask,
+        Section::Show,
+        Section::Jobs,
+        Section::Saved,
+    ];
+
+    // Sections backed by a live HN feed.
+    pub const FEEDS: [Section; 4] = [Section::Top, Section::Ask, Section::Show, Section::Jobs];
+
     pub fn as_str(&self) -> &str {
         match self {
             Section::Top => "Top",
             Section::Ask => "Ask",
             Section::Show => "Show",
             Section::Jobs => "Jobs",
+            Section::Saved => "Saved",
         }
     }
 
-    pub fn get_api_url(&self) -> String {
+    pub fn next(&self) -> Section {
         match self {
-            Section::Top => "https://hacker-news.firebaseio.com/v0/topstories.json".to_string(),
-            Section::Ask => "https://hacker-news.firebaseio.com/v0/askstories.json".to_string(),
-            Section::Show => "https://hacker-news.firebaseio.com/v0/showstories.json".to_string(),
-            Section::Jobs => "https://hacker-news.firebaseio.com/v0/jobstories.json".to_string(),
+            Section::Top => Section::Ask,
+            Section::Ask => Section::Show,
+            Section::Show => Section::Jobs,
+            Section::Jobs => Section::Saved,
+            Section::Saved => Section::Top,
         }
     }
+
+    pub fn previous(&self) -> Section {
+        match self {
+            Section::Top => Section::Saved,
+            Section::Saved => Section::Jobs,
+            Section::Jobs => Section::Show,
+            Section::Show => Section::Ask,
+            Section::Ask => Section::Top,
+        }
+    }
+
+    pub fn get_api_url(&self) -> Option<String> {
+        let url = match self {
+            Section::Top => "https://hacker-news.firebaseio.com/v0/topstories.json",
+            Section::Ask => "https://hacker-news.firebaseio.com/v0/askstories.json",
+            Section::Show => "https://hacker-news.firebaseio.com/v0/showstories.json",
+            Section::Jobs => "https://hacker-news.firebaseio.com/v0/jobstories.json",
+            Section::Saved => return None,
+        };
+        Some(url.to_string())
+    }
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
 pub enum Mode {
     Normal,
     Menu,
@@ -72,6 +217,72 @@ pub enum Mode {
     Comments,
     CommandPalette,
     Search,
+    Prompt,
+    // Full-history search through the HN Search API.
+    RemoteSearch,
+    // Typing a search over the open thread.
+    CommentSearch,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 9] = [
+        Mode::Normal,
+        Mode::Menu,
+        Mode::Summary,
+        Mode::Comments,
+        Mode::CommandPalette,
+        Mode::Search,
+        Mode::Prompt,
+        Mode::RemoteSearch,
+        Mode::CommentSearch,
+    ];
+
+    // Name used for this mode in the config file's `[keys.<mode>]` tables.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Mode::Normal => "normal",
+            Mode::Menu => "menu",
+            Mode::Summary => "summary",
+            Mode::Comments => "comments",
+            Mode::CommandPalette => "palette",
+            Mode::Search => "search",
+            Mode::Prompt => "prompt",
+            Mode::RemoteSearch => "remote-search",
+            Mode::CommentSearch => "comment-search",
+        }
+    }
+
+    // Modes where unbound printable keys are typed into an input field.
+    pub fn takes_text_input(&self) -> bool {
+        matches!(
+            self,
+            Mode::CommandPalette
+                | Mode::Search
+                | Mode::Prompt
+                | Mode::RemoteSearch
+                | Mode::CommentSearch
+        )
+    }
+}
+
+// What a line of text typed into `Mode::Prompt` will be used for.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum PromptKind {
+    BookmarkTags,
+    BookmarkNote,
+    MutePattern,
+    ExportPath,
+    GoTo,
+    LoginUser,
+    LoginPassword,
+}
+
+pub struct Prompt {
+    pub kind: PromptKind,
+    pub label: String,
+    pub input: String,
+    // Mode to go back to once the prompt is confirmed or cancelled.
+    pub return_mode: Mode,
 }
 
 #[derive(Serialize)]
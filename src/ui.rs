@@ -2,53 +2,118 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    widgets::{Block, Borders, Clear, Gauge, List, ListItem, ListState, Paragraph},
     Frame,
 };
 
+use std::time::Duration;
+
+use crate::algolia::format_date;
 use crate::app::App;
-use crate::types::Mode;
+use crate::bookmarks::Bookmark;
+use crate::diagnostics;
+use crate::keymap::Action;
+use crate::theme::Theme;
+use crate::types::{Mode, PollOption, PromptKind, Section};
+
+// Screen regions from the last draw, used to map mouse clicks back to the
+// items that were under the pointer.
+#[derive(Default)]
+pub struct HitMap {
+    pub tabs: Vec<(Rect, Section)>,
+    pub stories: Rect,
+    pub comments: Rect,
+    // Line range of every comment in the rendered thread, and the first
+    // visible line.
+    pub comment_lines: Vec<(usize, usize)>,
+    pub comments_scroll: usize,
+    pub search_results: Rect,
+    pub menu: Rect,
+    pub palette: Rect,
+    pub palette_list: Rect,
+    pub palette_offset: usize,
+}
+
+// How long a status message stays in the help bar.
+const STATUS_TIMEOUT: Duration = Duration::from_secs(6);
+
+// The area inside a bordered block.
+fn inner(area: Rect) -> Rect {
+    Rect::new(
+        area.x.saturating_add(1),
+        area.y.saturating_add(1),
+        area.width.saturating_sub(2),
+        area.height.saturating_sub(2),
+    )
+}
 
-fn draw_help_bar(f: &mut Frame, area: Rect, shortcuts: &[(&str, &str)]) {
+fn draw_help_bar(f: &mut Frame, area: Rect, app: &App) {
+    let shortcuts = app.keymap.help_entries(app.mode);
+    let theme = &app.theme;
     let spans: Vec<Span> = shortcuts
         .iter()
         .enumerate()
         .flat_map(|(i, (key, desc))| {
             let mut s = vec![
-                Span::styled(
-                    format!(" {} ", key),
-                    Style::default()
-                        .fg(Color::Black)
-                        .bg(Color::Green)
-                        .add_modifier(Modifier::BOLD),
-                ),
-                Span::styled(format!(" {} ", desc), Style::default().fg(Color::DarkGray)),
+                Span::styled(format!(" {} ", key), theme.help_key),
+                Span::styled(format!(" {} ", desc), theme.help_text),
             ];
             if i < shortcuts.len() - 1 {
-                s.push(Span::styled(" ", Style::default().fg(Color::DarkGray)));
+                s.push(Span::styled(" ", theme.help_text));
             }
             s
         })
         .collect();
 
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(theme.muted);
+    if let Some((message, at)) = &app.status_message {
+        if at.elapsed() < STATUS_TIMEOUT {
+            block = block.title_bottom(
+                Line::from(Span::styled(format!(" {} ", message), theme.highlight)).right_aligned(),
+            );
+        }
+    }
     let help = Paragraph::new(Line::from(spans))
         .style(Style::default())
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::DarkGray)),
-        );
+        .block(block);
     f.render_widget(help, area);
 }
 
 pub fn draw_ui(f: &mut Frame, app: &mut App) {
-    if app.mode == Mode::Comments {
+    draw_screen(f, app);
+    if app.show_debug {
+        draw_debug(f, app);
+    }
+}
+
+fn draw_screen(f: &mut Frame, app: &mut App) {
+    app.hit_map = HitMap::default();
+    let base_mode = app.base_mode();
+    if base_mode == Mode::RemoteSearch {
+        draw_remote_search(f, app);
+        if app.mode == Mode::CommandPalette {
+            draw_command_palette(f, app);
+        }
+        return;
+    }
+    if matches!(base_mode, Mode::Comments | Mode::CommentSearch) {
         draw_comments(f, app);
+        if app.mode == Mode::CommandPalette {
+            draw_command_palette(f, app);
+        }
+        if app.mode == Mode::Prompt {
+            draw_prompt(f, app);
+        }
         return;
     }
 
+    let theme = app.theme.clone();
     let help_height = 3;
-    let search_height = if app.mode == Mode::Search { 3 } else { 0 };
+    // A committed filter stays on screen while browsing the filtered list.
+    let filtering = base_mode == Mode::Search || !app.search_query.is_empty();
+    let search_height = if filtering { 3 } else { 0 };
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -63,33 +128,47 @@ pub fn draw_ui(f: &mut Frame, app: &mut App) {
 
     // Title bar
     let title = Paragraph::new(app.app_name.clone())
-        .style(Style::default().fg(Color::Green))
+        .style(theme.title)
         .alignment(Alignment::Center)
         .block(Block::default().borders(Borders::ALL));
     f.render_widget(title, chunks[0]);
 
     // Section menu
-    let sections = ["Top", "Ask", "Show", "Jobs"];
-    let section_spans: Vec<Span> = sections
+    let section_spans: Vec<Span> = Section::ALL
         .iter()
         .map(|&section| {
-            if section == app.current_section.as_str() {
-                Span::styled(
-                    format!(" {} ", section),
-                    Style::default()
-                        .fg(Color::Green)
-                        .add_modifier(Modifier::REVERSED),
-                )
+            let label = format!(" {} ", section.as_str());
+            if section == app.current_section {
+                Span::styled(label, theme.tab_active)
             } else {
-                Span::styled(format!(" {} ", section), Style::default().fg(Color::Green))
+                Span::styled(label, theme.tab)
             }
         })
         .collect();
 
+    // Remember where each (centered) tab landed so it can be clicked
+    let tabs_area = inner(chunks[1]);
+    let tabs_width: u16 = section_spans.iter().map(|s| s.width() as u16).sum();
+    let mut x = tabs_area.x + tabs_area.width.saturating_sub(tabs_width) / 2;
+    for (span, &section) in section_spans.iter().zip(Section::ALL.iter()) {
+        let width = span.width() as u16;
+        app.hit_map
+            .tabs
+            .push((Rect::new(x, tabs_area.y, width, 1), section));
+        x += width;
+    }
+
+    let sort = if app.current_section == Section::Saved {
+        "saved"
+    } else {
+        app.sort.as_str()
+    };
     let section_menu = Paragraph::new(Line::from(section_spans))
-        .style(Style::default().fg(Color::Green))
+        .style(theme.tab)
         .alignment(Alignment::Center)
-        .block(Block::default().borders(Borders::ALL));
+        .block(Block::default().borders(Borders::ALL).title_top(
+            Line::from(Span::styled(format!(" sort: {} ", sort), theme.muted)).right_aligned(),
+        ));
     f.render_widget(section_menu, chunks[1]);
 
     // Stories list
@@ -99,76 +178,126 @@ pub fn draw_ui(f: &mut Frame, app: &mut App) {
     let visible_stories: Vec<ListItem> = app
         .filtered_stories
         .iter()
-        .map(|&i| &app.stories[i])
+        .map(|&i| (i, &app.stories[i]))
         .enumerate()
         .skip(app.scroll_offset)
         .take(visible_height)
-        .map(|(i, story)| {
+        .map(|(i, (story_index, story))| {
             let comment_str = if story.descendants > 0 {
                 format!(" | {} comments", story.descendants)
             } else {
                 String::new()
             };
-            let content = Line::from(vec![Span::raw(format!(
+            let mut spans = vec![Span::raw(format!(
                 "{:2}. {} [{}] ({}){}",
                 i + 1,
                 story.title,
                 story.score,
                 story.by,
                 comment_str
-            ))]);
-            ListItem::new(content).style(Style::default().fg(Color::Green).add_modifier(
-                if i == app.selected_index {
-                    Modifier::REVERSED
+            ))];
+            if app.account.is_upvoted(story.id) {
+                spans.push(Span::styled(" ▲", theme.highlight));
+            }
+            if let Some(new) = app.history.new_comments(story) {
+                spans.push(Span::styled(format!(" +{} new", new), theme.highlight));
+            }
+            if app.current_section == Section::Saved {
+                if let Some(bookmark) = app.bookmarks.items.get(story_index) {
+                    spans.extend(bookmark_details(bookmark, &theme));
+                }
+            }
+            let muted = app.show_hidden && app.hides_story(story);
+            if muted {
+                // Mute rules are local; hiding on HN is the account's.
+                let label = if app.filters.hides_story(story) {
+                    " [muted]"
                 } else {
-                    Modifier::empty()
-                },
-            ))
+                    " [hidden]"
+                };
+                spans.push(Span::styled(label, theme.muted));
+            }
+            let content = Line::from(spans);
+            ListItem::new(content).style(if story_index == app.selected_index {
+                theme.selected
+            } else if muted {
+                theme.muted
+            } else if app.history.is_visited(story.id) {
+                theme.visited
+            } else {
+                theme.text
+            })
         })
         .collect();
 
+    let mut stories_block = Block::default().borders(Borders::ALL);
+    if app.hidden_count > 0 {
+        let toggle = app
+            .keymap
+            .key_label(Mode::Normal, Action::ToggleHidden)
+            .map(|key| {
+                format!(
+                    ", {} to {}",
+                    key,
+                    if app.show_hidden { "hide" } else { "show" }
+                )
+            })
+            .unwrap_or_default();
+        let state = if app.show_hidden { "shown" } else { "hidden" };
+        stories_block = stories_block.title_top(
+            Line::from(Span::styled(
+                format!(" {} muted {}{} ", app.hidden_count, state, toggle),
+                theme.muted,
+            ))
+            .right_aligned(),
+        );
+    }
     let stories_list = List::new(visible_stories)
-        .block(Block::default().borders(Borders::ALL))
-        .style(Style::default().fg(Color::Green));
+        .block(stories_block)
+        .style(theme.text);
     f.render_widget(stories_list, chunks[2]);
+    app.hit_map.stories = inner(chunks[2]);
 
     // Search box
-    if app.mode == Mode::Search {
+    if filtering {
+        let shown = format!(" {}/{} ", app.filtered_stories.len(), app.stories.len());
+        let hint = match &app.search_error {
+            Some(e) => Span::styled(format!(" {} ", e), theme.highlight),
+            None if base_mode != Mode::Search => {
+                let clear = app
+                    .keymap
+                    .key_label(Mode::Normal, Action::Back)
+                    .map(|key| format!("{} to clear ", key))
+                    .unwrap_or_default();
+                Span::styled(format!("{}{}", shown, clear), theme.muted)
+            }
+            None => Span::styled(
+                " by:user  domain:site  score>N  comments>N  \"phrase\"  -word ",
+                theme.muted,
+            ),
+        };
         let search_input = Paragraph::new(format!("/{}", app.search_query))
-            .style(Style::default().fg(Color::Green))
+            .style(theme.text)
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title("Search")
-                    .border_style(Style::default().fg(Color::Green)),
+                    .title(if base_mode == Mode::Search {
+                        "Search"
+                    } else {
+                        "Filter"
+                    })
+                    .title_bottom(Line::from(hint).right_aligned())
+                    .border_style(if app.search_error.is_some() {
+                        theme.highlight
+                    } else {
+                        theme.text
+                    }),
             );
         f.render_widget(search_input, chunks[3]);
     }
 
     // Help bar
-    if app.mode == Mode::Search {
-        draw_help_bar(
-            f,
-            chunks[4],
-            &[("↑↓", "navigate"), ("Enter", "open"), ("Esc", "cancel")],
-        );
-    } else {
-        draw_help_bar(
-            f,
-            chunks[4],
-            &[
-                ("j/k", "navigate"),
-                ("h/l", "sections"),
-                ("Enter", "open"),
-                ("c", "comments"),
-                ("o", "options"),
-                ("/", "search"),
-                ("Ctrl+K", "palette"),
-                ("r", "refresh"),
-                ("q", "quit"),
-            ],
-        );
-    }
+    draw_help_bar(f, chunks[4], app);
 
     // Overlays (drawn on top)
     if app.show_menu {
@@ -176,15 +305,42 @@ pub fn draw_ui(f: &mut Frame, app: &mut App) {
     }
 
     if let Some(summary) = &app.claude_summary {
-        draw_summary(f, summary);
+        draw_summary(f, summary, &theme);
     }
 
     if app.mode == Mode::CommandPalette {
         draw_command_palette(f, app);
     }
+
+    if app.mode == Mode::Prompt {
+        draw_prompt(f, app);
+    }
 }
 
-fn strip_html(html: &str) -> String {
+// What a "Saved" row adds after the story: the bookmarked comment's author,
+// the tags and the note.
+fn bookmark_details(bookmark: &Bookmark, theme: &Theme) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    if let Some(comment) = &bookmark.comment {
+        spans.push(Span::styled(
+            format!(" | comment by {}", comment.by),
+            theme.author,
+        ));
+    }
+    if !bookmark.tags.is_empty() {
+        let tags: Vec<String> = bookmark.tags.iter().map(|t| format!("#{}", t)).collect();
+        spans.push(Span::styled(
+            format!(" {}", tags.join(" ")),
+            theme.highlight,
+        ));
+    }
+    if !bookmark.note.is_empty() {
+        spans.push(Span::styled(format!(" — {}", bookmark.note), theme.muted));
+    }
+    spans
+}
+
+pub fn strip_html(html: &str) -> String {
     let mut result = String::new();
     let mut in_tag = false;
     let mut chars = html.chars().peekable();
@@ -241,40 +397,120 @@ fn strip_html(html: &str) -> String {
     result
 }
 
+// Byte ranges of `query` (already lowercase) in `text`, ignoring ASCII case.
+fn find_matches(text: &str, query: &str) -> Vec<(usize, usize)> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let lower = text.to_ascii_lowercase();
+    lower
+        .match_indices(query)
+        .map(|(i, m)| (i, i + m.len()))
+        .collect()
+}
+
+// Byte length of the first wrapped line of `text` at most `width` characters
+// wide, broken after the last space if there is one. Always a char boundary,
+// and never 0 for non-empty text.
+fn wrap_end(text: &str, width: usize) -> usize {
+    let Some((limit, _)) = text.char_indices().nth(width.max(1)) else {
+        return text.len();
+    };
+    text[..limit].rfind(' ').map_or(limit, |p| p + 1)
+}
+
+// Splits `chunk`, which starts at byte `offset` of the line `matches` were
+// found in, into spans with the matched parts highlighted.
+fn highlight_spans(
+    chunk: &str,
+    offset: usize,
+    matches: &[(usize, usize)],
+    style: Style,
+    highlight: Style,
+) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    for &(start, end) in matches {
+        let start = start.saturating_sub(offset).min(chunk.len());
+        let end = end.saturating_sub(offset).min(chunk.len());
+        if end <= pos {
+            continue;
+        }
+        let start = start.max(pos);
+        if start > pos {
+            spans.push(Span::styled(chunk[pos..start].to_string(), style));
+        }
+        spans.push(Span::styled(chunk[start..end].to_string(), highlight));
+        pos = end;
+    }
+    if pos < chunk.len() || spans.is_empty() {
+        spans.push(Span::styled(chunk[pos..].to_string(), style));
+    }
+    spans
+}
+
 fn draw_comments(f: &mut Frame, app: &mut App) {
+    let theme = app.theme.clone();
+    let searching = app.mode == Mode::CommentSearch || !app.comment_query.is_empty();
+    // At most this many poll options get a bar, to leave room for comments.
+    let poll_height = match app.poll_options.len() {
+        0 => 0,
+        n => n.min(8) as u16 + 2,
+    };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(3), // Title bar
-            Constraint::Min(0),    // Comments
-            Constraint::Length(3), // Help bar
+            Constraint::Length(3),                             // Title bar
+            Constraint::Length(poll_height),                   // Poll options
+            Constraint::Min(0),                                // Comments
+            Constraint::Length(if searching { 3 } else { 0 }), // Search box
+            Constraint::Length(3),                             // Help bar
         ])
         .split(f.area());
 
     // Title bar with story title
-    let title = Paragraph::new(format!("Comments: {}", app.comments_story_title))
-        .style(Style::default().fg(Color::Green))
+    let story_title = app.comments_story.as_ref().map_or("", |s| s.title.as_str());
+    let new_count = app.new_comments.len();
+    let title_text = if new_count > 0 {
+        format!("Comments: {} ({} new)", story_title, new_count)
+    } else {
+        format!("Comments: {}", story_title)
+    };
+    let title = Paragraph::new(title_text)
+        .style(theme.title)
         .alignment(Alignment::Center)
         .block(Block::default().borders(Borders::ALL));
     f.render_widget(title, chunks[0]);
 
+    if poll_height > 0 {
+        draw_poll(f, chunks[1], &app.poll_options, &theme);
+    }
+
     if app.comments.is_empty() {
         let empty = Paragraph::new("No comments yet.")
-            .style(Style::default().fg(Color::Green))
+            .style(theme.text)
             .alignment(Alignment::Center)
             .block(Block::default().borders(Borders::ALL));
-        f.render_widget(empty, chunks[1]);
+        f.render_widget(empty, chunks[2]);
     } else {
-        let visible_height = (chunks[1].height as usize).saturating_sub(2);
-        let available_width = chunks[1].width.saturating_sub(2) as usize;
+        let visible_height = (chunks[2].height as usize).saturating_sub(2);
+        let available_width = chunks[2].width.saturating_sub(2) as usize;
 
         // Pass 1: build all lines and track line ranges per comment
         let mut all_lines: Vec<Line> = Vec::new();
         // (start_line, end_line) for each comment index
         let mut comment_ranges: Vec<(usize, usize)> = Vec::new();
+        let hidden = app.hidden_comments();
+        let query = app.comment_query.to_ascii_lowercase();
+        let match_style = theme.highlight.add_modifier(Modifier::REVERSED);
 
-        for fc in app.comments.iter() {
+        for (i, fc) in app.comments.iter().enumerate() {
             let start = all_lines.len();
+            // Replies folded under a collapsed comment take no lines.
+            if hidden[i] {
+                comment_ranges.push((start, start));
+                continue;
+            }
             let indent = "  ".repeat(fc.depth);
             let tree_char = if fc.depth > 0 { "| " } else { "" };
             let prefix = format!("{}{}", indent, tree_char);
@@ -287,16 +523,43 @@ fn draw_comments(f: &mut Frame, app: &mut App) {
                 .map(strip_html)
                 .unwrap_or_default();
 
-            // Header line
-            all_lines.push(Line::from(vec![
-                Span::styled(prefix.clone(), Style::default().fg(Color::DarkGray)),
-                Span::styled(
-                    format!("{} :", fc.comment.by),
-                    Style::default()
-                        .fg(Color::Yellow)
-                        .add_modifier(Modifier::BOLD),
-                ),
-            ]));
+            // Header line, flagged when the comment is new since the last visit
+            let muted = app.filters.hides_user(&fc.comment.by);
+            let author_style = if muted { theme.muted } else { theme.author };
+            let author_matches = find_matches(&fc.comment.by, &query);
+            let mut header = vec![Span::styled(prefix.clone(), theme.tree_gutter)];
+            header.extend(highlight_spans(
+                &fc.comment.by,
+                0,
+                &author_matches,
+                author_style,
+                match_style,
+            ));
+            if app.account.is_upvoted(fc.comment.id) {
+                header.push(Span::styled(" ▲", theme.highlight));
+            }
+            header.push(Span::styled(" :", author_style));
+            if app.collapsed.contains(&fc.comment.id) {
+                header.push(Span::styled(
+                    format!(" [+{}]", app.reply_count(i)),
+                    theme.muted,
+                ));
+            }
+            if app.new_comments.contains(&fc.comment.id) {
+                header.push(Span::styled(" [new]", theme.highlight));
+            }
+            if muted {
+                header.push(Span::styled(" [muted]", theme.muted));
+            }
+            all_lines.push(Line::from(header));
+
+            // Comments from muted users keep their place in the thread but
+            // not their text, unless muted items are revealed.
+            if muted && !app.show_hidden {
+                all_lines.push(Line::from(Span::raw("")));
+                comment_ranges.push((start, all_lines.len()));
+                continue;
+            }
 
             // Wrapped text lines
             let wrap_width = available_width.saturating_sub(prefix_len).max(20);
@@ -305,25 +568,24 @@ fn draw_comments(f: &mut Frame, app: &mut App) {
                     all_lines.push(Line::from(Span::raw("")));
                     continue;
                 }
+                let matches = find_matches(text_line, &query);
                 let mut remaining = text_line;
                 while !remaining.is_empty() {
-                    let end = if remaining.len() <= wrap_width {
-                        remaining.len()
-                    } else {
-                        remaining[..wrap_width]
-                            .rfind(' ')
-                            .map(|p| p + 1)
-                            .unwrap_or(wrap_width)
-                    };
+                    let offset = text_line.len() - remaining.len();
+                    let end = wrap_end(remaining, wrap_width);
                     let (chunk, rest) = remaining.split_at(end);
-                    all_lines.push(Line::from(vec![
-                        Span::styled(
-                            "  ".repeat(fc.depth).to_string()
-                                + if fc.depth > 0 { "| " } else { "" },
-                            Style::default().fg(Color::DarkGray),
-                        ),
-                        Span::styled(chunk.to_string(), Style::default().fg(Color::Green)),
-                    ]));
+                    let mut line = vec![Span::styled(
+                        "  ".repeat(fc.depth).to_string() + if fc.depth > 0 { "| " } else { "" },
+                        theme.tree_gutter,
+                    )];
+                    line.extend(highlight_spans(
+                        chunk,
+                        offset,
+                        &matches,
+                        theme.comment_text,
+                        match_style,
+                    ));
+                    all_lines.push(Line::from(line));
                     remaining = rest;
                 }
             }
@@ -360,8 +622,7 @@ fn draw_comments(f: &mut Frame, app: &mut App) {
                     .get(app.comments_selected)
                     .is_some_and(|&(s, e)| line_idx >= s && line_idx < e);
                 if is_selected {
-                    line.clone()
-                        .patch_style(Style::default().bg(Color::Rgb(0, 40, 0)))
+                    line.clone().patch_style(theme.comment_selected)
                 } else {
                     line.clone()
                 }
@@ -371,24 +632,200 @@ fn draw_comments(f: &mut Frame, app: &mut App) {
         let comments_widget = Paragraph::new(visible_lines)
             .block(Block::default().borders(Borders::ALL))
             .style(Style::default());
-        f.render_widget(comments_widget, chunks[1]);
+        f.render_widget(comments_widget, chunks[2]);
+
+        app.hit_map.comments = inner(chunks[2]);
+        app.hit_map.comment_lines = comment_ranges;
+        app.hit_map.comments_scroll = app.comments_scroll;
+    }
+
+    // Search box
+    if searching {
+        let status = if app.comment_query.is_empty() {
+            String::new()
+        } else if app.comment_matches.is_empty() {
+            " no matches ".to_string()
+        } else {
+            let current = app
+                .comment_matches
+                .iter()
+                .position(|&i| i == app.comments_selected)
+                .map_or("-".to_string(), |i| (i + 1).to_string());
+            format!(" {}/{} matches ", current, app.comment_matches.len())
+        };
+        let search_input = Paragraph::new(format!("/{}", app.comment_query))
+            .style(theme.text)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Search thread")
+                    .title_bottom(Line::styled(status, theme.muted).right_aligned())
+                    .border_style(if app.mode == Mode::CommentSearch {
+                        theme.highlight
+                    } else {
+                        theme.text
+                    }),
+            );
+        f.render_widget(search_input, chunks[3]);
     }
 
     // Help bar
-    draw_help_bar(
-        f,
-        chunks[2],
-        &[
-            ("j/k", "navigate"),
-            ("o", "open"),
-            ("r", "reply"),
-            ("R", "refresh"),
-            ("Esc", "back"),
-        ],
-    );
+    draw_help_bar(f, chunks[4], app);
 }
 
-fn draw_menu(f: &mut Frame, app: &App) {
+// A poll's options as bars showing each one's share of the votes.
+fn draw_poll(f: &mut Frame, area: Rect, options: &[PollOption], theme: &Theme) {
+    let total: i32 = options.iter().map(|o| o.score.max(0)).sum();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Poll: {} votes", total))
+        .border_style(theme.text);
+    let body = block.inner(area);
+    f.render_widget(block, area);
+
+    let labels: Vec<String> = options.iter().map(|o| strip_html(&o.text)).collect();
+    let longest = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let label_width = (longest as u16 + 1).min(body.width / 2);
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Constraint::Length(1); options.len()])
+        .split(body);
+    for ((option, label), row) in options.iter().zip(labels).zip(rows.iter()) {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(label_width), Constraint::Min(0)])
+            .split(*row);
+        f.render_widget(Paragraph::new(label).style(theme.text), columns[0]);
+        let ratio = if total > 0 {
+            option.score.max(0) as f64 / total as f64
+        } else {
+            0.0
+        };
+        let gauge = Gauge::default()
+            .gauge_style(theme.highlight)
+            .ratio(ratio)
+            .label(format!("{} ({:.0}%)", option.score, ratio * 100.0));
+        f.render_widget(gauge, columns[1]);
+    }
+}
+
+fn draw_remote_search(f: &mut Frame, app: &mut App) {
+    let theme = app.theme.clone();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // Title bar
+            Constraint::Min(0),    // Results
+            Constraint::Length(3), // Search box
+            Constraint::Length(3), // Help bar
+        ])
+        .split(f.area());
+
+    let title = Paragraph::new("Search Hacker News")
+        .style(theme.title)
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(title, chunks[0]);
+
+    let search = &mut app.remote_search;
+    let visible_height = (chunks[1].height as usize).saturating_sub(2);
+    let width = chunks[1].width.saturating_sub(2) as usize;
+    search.ensure_visible(visible_height);
+
+    let items: Vec<ListItem> = search
+        .results
+        .iter()
+        .enumerate()
+        .skip(search.scroll)
+        .take(visible_height)
+        .map(|(i, hit)| {
+            let date = format_date(hit.created_at_i);
+            let mut spans = if hit.is_comment() {
+                let snippet = hit
+                    .comment_text
+                    .as_deref()
+                    .map(strip_html)
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let head = format!(
+                    "{:2}. {} on \"{}\" · {} ",
+                    i + 1,
+                    hit.author,
+                    hit.story_title.as_deref().unwrap_or(""),
+                    date
+                );
+                let room = width.saturating_sub(head.chars().count());
+                vec![
+                    Span::raw(head),
+                    Span::styled(snippet.chars().take(room).collect::<String>(), theme.muted),
+                ]
+            } else {
+                vec![Span::raw(format!(
+                    "{:2}. {} [{}] ({}) | {} comments · {}",
+                    i + 1,
+                    hit.title.as_deref().unwrap_or(""),
+                    hit.points.unwrap_or_default(),
+                    hit.author,
+                    hit.num_comments.unwrap_or_default(),
+                    date
+                ))]
+            };
+            if hit.id().is_some_and(|id| app.history.is_visited(id)) {
+                spans[0].style = theme.visited;
+            }
+            ListItem::new(Line::from(spans)).style(if i == search.selected {
+                theme.selected
+            } else {
+                theme.text
+            })
+        })
+        .collect();
+
+    let results_title = if search.last_run.is_some() {
+        format!(" {} of {} results ", search.results.len(), search.total)
+    } else {
+        String::new()
+    };
+    let results = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(results_title))
+        .style(theme.text);
+    f.render_widget(results, chunks[1]);
+    app.hit_map.search_results = inner(chunks[1]);
+
+    let search = &app.remote_search;
+    let stale = if search.is_stale() && search.last_run.is_some() {
+        " (Enter to update)"
+    } else {
+        ""
+    };
+    let input = Paragraph::new(format!("{}_", search.input))
+        .style(theme.text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(
+                    "Search {} by {}{}",
+                    search.kind.as_str(),
+                    search.sort.as_str(),
+                    stale
+                ))
+                .title_bottom(
+                    Line::from(Span::styled(
+                        " points>N  after:YYYY-MM-DD  before:YYYY-MM-DD ",
+                        theme.muted,
+                    ))
+                    .right_aligned(),
+                )
+                .border_style(theme.text),
+        );
+    f.render_widget(input, chunks[2]);
+
+    draw_help_bar(f, chunks[3], app);
+}
+
+fn draw_menu(f: &mut Frame, app: &mut App) {
     let overlay = Block::default().style(Style::default());
     f.render_widget(overlay, f.area());
 
@@ -403,24 +840,23 @@ fn draw_menu(f: &mut Frame, app: &App) {
         .iter()
         .enumerate()
         .map(|(i, &item)| {
-            ListItem::new(item).style(Style::default().fg(Color::Green).add_modifier(
-                if i == app.menu_index {
-                    Modifier::REVERSED
-                } else {
-                    Modifier::empty()
-                },
-            ))
+            ListItem::new(item).style(if i == app.menu_index {
+                app.theme.selected
+            } else {
+                app.theme.text
+            })
         })
         .collect();
 
     let menu = List::new(items)
         .block(Block::default().borders(Borders::ALL).title("Options"))
-        .style(Style::default().fg(Color::Green))
-        .highlight_style(Style::default().bg(Color::Green));
+        .style(app.theme.text)
+        .highlight_style(app.theme.popup_selected);
     f.render_widget(menu, area);
+    app.hit_map.menu = inner(area);
 }
 
-fn draw_summary(f: &mut Frame, summary: &str) {
+fn draw_summary(f: &mut Frame, summary: &str, theme: &Theme) {
     let area = centered_rect(80, 60, f.area());
 
     let summary_widget = Paragraph::new(summary)
@@ -429,51 +865,166 @@ fn draw_summary(f: &mut Frame, summary: &str) {
                 .borders(Borders::ALL)
                 .title("Claude Summary"),
         )
-        .style(Style::default().fg(Color::Green).bg(Color::Reset))
+        .style(theme.text.bg(Color::Reset))
         .wrap(ratatui::widgets::Wrap { trim: true });
     f.render_widget(summary_widget, area);
 }
 
-fn draw_command_palette(f: &mut Frame, app: &App) {
+fn draw_command_palette(f: &mut Frame, app: &mut App) {
+    let theme = &app.theme;
     let area = centered_rect(60, 30, f.area());
 
-    let search_input = Paragraph::new(app.command_palette.search_query.clone())
-        .style(Style::default().fg(Color::Green))
+    let palette = &app.command_palette;
+    let search_input = Paragraph::new(palette.search_query.clone())
+        .style(theme.text)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Command Palette")
-                .border_style(Style::default().fg(Color::Green)),
+                .title(format!("Command Palette ({})", palette.context.as_str()))
+                .border_style(theme.text),
         );
     f.render_widget(search_input, Rect::new(area.x, area.y, area.width, 3));
 
-    let commands_area = Rect::new(area.x, area.y + 3, area.width, area.height - 3);
-    let items: Vec<ListItem> = app
-        .command_palette
-        .filtered_commands
+    let commands_area = Rect::new(
+        area.x,
+        area.y + 3,
+        area.width,
+        area.height.saturating_sub(3),
+    );
+    let items: Vec<ListItem> = palette
+        .matches
         .iter()
-        .map(|&idx| {
-            let cmd = &app.command_palette.commands[idx];
-            let content = vec![Line::from(vec![
-                Span::styled(cmd.name.clone(), Style::default().fg(Color::Green)),
-                Span::raw(" "),
-                Span::styled(
-                    cmd.description.clone(),
-                    Style::default().fg(Color::DarkGray),
-                ),
-            ])];
-            ListItem::new(content)
+        .map(|m| {
+            let entry = &palette.entries[m.entry];
+            // Matched characters of the name are highlighted
+            let mut spans: Vec<Span> = entry
+                .name
+                .chars()
+                .enumerate()
+                .map(|(i, c)| {
+                    let style = if m.positions.contains(&i) {
+                        theme.highlight
+                    } else {
+                        theme.text
+                    };
+                    Span::styled(c.to_string(), style)
+                })
+                .collect();
+            spans.push(Span::raw(" "));
+            spans.push(Span::styled(entry.description, theme.muted));
+            if let Some(key) = app.keymap.key_label(palette.context, entry.action) {
+                spans.push(Span::styled(format!("  [{}]", key), theme.muted));
+            }
+            ListItem::new(Line::from(spans))
         })
         .collect();
 
     let commands_list = List::new(items)
         .block(Block::default().borders(Borders::ALL))
-        .highlight_style(Style::default().fg(Color::Black).bg(Color::Green))
+        .highlight_style(theme.popup_selected)
         .highlight_symbol("> ");
 
     let mut list_state = ListState::default();
-    list_state.select(Some(app.command_palette.selected_index));
+    list_state.select(Some(palette.selected_index));
     f.render_stateful_widget(commands_list, commands_area, &mut list_state);
+
+    app.hit_map.palette = area;
+    app.hit_map.palette_list = inner(commands_area);
+    app.hit_map.palette_offset = list_state.offset();
+}
+
+fn draw_prompt(f: &mut Frame, app: &App) {
+    let Some(prompt) = &app.prompt else {
+        return;
+    };
+    let theme = &app.theme;
+    let full = centered_rect(60, 20, f.area());
+    let area = Rect::new(full.x, full.y, full.width, 3).intersection(f.area());
+    let shown = if prompt.kind == PromptKind::LoginPassword {
+        "*".repeat(prompt.input.chars().count())
+    } else {
+        prompt.input.clone()
+    };
+    let input = Paragraph::new(format!("{}_", shown))
+        .style(theme.text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(prompt.label.clone())
+                .border_style(theme.text),
+        );
+    f.render_widget(Clear, area);
+    f.render_widget(input, area);
+}
+
+// Request log, running tasks and error counts, for telling a slow fetch
+// from a failing one.
+fn draw_debug(f: &mut Frame, app: &App) {
+    let theme = &app.theme;
+    let stats = diagnostics::snapshot();
+    let area = centered_rect(80, 70, f.area());
+
+    let errors = if stats.errors.is_empty() {
+        "none".to_string()
+    } else {
+        stats
+            .errors
+            .iter()
+            .map(|(kind, n)| format!("{} {}", kind, n))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("Requests ", theme.muted),
+            Span::styled(stats.total_requests.to_string(), theme.text),
+            Span::styled("  Cache hits ", theme.muted),
+            Span::styled(stats.cache_hits.to_string(), theme.text),
+            Span::styled("  Errors ", theme.muted),
+            Span::styled(errors, theme.text),
+        ]),
+        Line::default(),
+        Line::styled(format!("In flight ({})", stats.tasks.len()), theme.title),
+    ];
+    for task in &stats.tasks {
+        lines.push(Line::styled(
+            format!("{:>6.1}s  {}", task.elapsed.as_secs_f64(), task.name),
+            theme.text,
+        ));
+    }
+    lines.push(Line::default());
+    lines.push(Line::styled("Recent requests", theme.title));
+    for request in &stats.requests {
+        let failed = request.error.is_some() || request.status.is_some_and(|s| s >= 400);
+        let status = match request.status {
+            Some(status) => status.to_string(),
+            None => "---".to_string(),
+        };
+        let mut text = format!(
+            "{} {:>6}ms  {} {}",
+            status,
+            request.latency.as_millis(),
+            request.method,
+            request.url
+        );
+        if let Some(error) = &request.error {
+            text.push_str(&format!("  ({})", error));
+        }
+        lines.push(Line::styled(
+            text,
+            if failed { theme.highlight } else { theme.text },
+        ));
+    }
+
+    let title = match app.keymap.key_label(app.base_mode(), Action::ToggleDebug) {
+        Some(key) => format!("Debug ({} to close)", key),
+        None => "Debug".to_string(),
+    };
+    let overlay = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .style(theme.text);
+    f.render_widget(Clear, area);
+    f.render_widget(overlay, area);
 }
 
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
@@ -495,3 +1046,22 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         ])
         .split(popup_layout[1])[1]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_multibyte_text_on_char_boundaries() {
+        assert_eq!(wrap_end("short", 20), 5);
+        assert_eq!(wrap_end("hello world again", 12), "hello world ".len());
+        // No space to break at: cut after `width` characters, not bytes.
+        let text = "ééééé";
+        assert_eq!(wrap_end(text, 3), "ééé".len());
+        let text = "日本語のテキスト です";
+        let end = wrap_end(text, 4);
+        assert!(text.is_char_boundary(end));
+        assert_eq!(&text[..end], "日本語の");
+        assert_eq!(wrap_end("€ €€€", 4), "€ ".len());
+    }
+}